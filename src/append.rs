@@ -0,0 +1,255 @@
+//! Growing existing NPY files along the outermost axis.
+
+use crate::element::{self, Element};
+use crate::error::{AppendError, ValidatePayloadError, WriteHeaderError};
+use crate::{writer, Header, HeaderInfo, WriterOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Appends rows to the file, which is written either by NumPy or by this crate,
+/// and returns the updated header.
+///
+/// The outermost axis is the first one in C order and the last one in Fortran order,
+/// and `data` should hold a whole number of rows in the order stored in the file.
+/// The elements are written in the byte order of the file.
+///
+/// The header is rewritten in-place when its padding can hold the longer shape,
+/// which is always the case for files written by `numpy.save` or by [`crate::write_header`]
+/// with the default options.
+/// Otherwise the whole file is rewritten to a temporary file with a larger header,
+/// which then replaces the original one.
+///
+/// The rows are written before the header, so that an interrupted append leaves bytes
+/// following the payload rather than a header describing missing rows.
+/// Such bytes are rejected by [`crate::validate_payload`] as trailing ones,
+/// but are overwritten (or discarded) by the next append.
+pub fn append_rows<T: Element, P: AsRef<std::path::Path>>(
+    path: P,
+    data: &[T],
+) -> Result<Header, AppendError> {
+    let path: &std::path::Path = path.as_ref();
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    let header_info: HeaderInfo =
+        crate::read_header_info(&mut file).map_err(ValidatePayloadError::from)?;
+    // bytes past the payload are what an interrupted append leaves, which are overwritten
+    let payload_end: u64 = (header_info.data_offset as u64)
+        .checked_add(header_info.payload_size as u64)
+        .ok_or_else(|| AppendError::SizeOverflow(header_info.header.shape.clone()))?;
+    let end: u64 = file.seek(SeekFrom::End(0i64))?;
+    if end < payload_end {
+        return Err(ValidatePayloadError::Truncated(
+            header_info.payload_size as u64,
+            end.saturating_sub(header_info.data_offset as u64),
+        )
+        .into());
+    }
+    let descr = &header_info.header.descr;
+    let byte_order = element::byte_order_of::<T>(descr)
+        .ok_or_else(|| AppendError::DTypeMismatch(descr.clone(), T::dtype()))?;
+    let header: Header = grow_shape(&header_info.header, data.len())?;
+    if data.is_empty() {
+        return Ok(header);
+    }
+    let mut buf = Vec::<u8>::new();
+    element::encode(data, byte_order, &mut buf);
+    match writer::prepare_header_in_place(
+        &header,
+        header_info.major_version,
+        header_info.header_len,
+    ) {
+        Ok(header_buf) => {
+            file.seek(SeekFrom::Start(payload_end))?;
+            file.write_all(&buf)?;
+            if payload_end + (buf.len() as u64) < end {
+                file.set_len(payload_end + buf.len() as u64)?;
+            }
+            file.seek(SeekFrom::Start(0u64))?;
+            file.write_all(&header_buf)?;
+            file.flush()?;
+        }
+        Err(WriteHeaderError::InsufficientSpace(..)) => {
+            rewrite(path, file, &header_info, &header, &buf)?;
+        }
+        Err(error) => return Err(error.into()),
+    }
+    Ok(header)
+}
+
+/// Extends the outermost axis by the rows held by `len` elements.
+fn grow_shape(header: &Header, len: usize) -> Result<Header, AppendError> {
+    let ndim: usize = header.shape.len();
+    let axis: usize = match ndim {
+        0usize => return Err(AppendError::NotAppendable(header.shape.clone())),
+        _ if header.fortran_order => ndim - 1usize,
+        _ => 0usize,
+    };
+    let row_len: usize = header
+        .shape
+        .iter()
+        .enumerate()
+        .filter(|&(n, _): &(usize, &usize)| n != axis)
+        .try_fold(1usize, |count: usize, (_, &n): (usize, &usize)| {
+            count.checked_mul(n)
+        })
+        .ok_or_else(|| AppendError::SizeOverflow(header.shape.clone()))?;
+    let mut header: Header = header.clone();
+    if len == 0usize {
+        return Ok(header);
+    }
+    if row_len == 0usize {
+        return Err(AppendError::NotAppendable(header.shape));
+    }
+    if !len.is_multiple_of(row_len) {
+        return Err(AppendError::IncompleteRow(len, row_len));
+    }
+    header.shape[axis] = header.shape[axis]
+        .checked_add(len / row_len)
+        .ok_or_else(|| AppendError::SizeOverflow(header.shape.clone()))?;
+    Ok(header)
+}
+
+/// Writes the new header, the existing payload and the appended one to a temporary file
+/// in the same directory, and renames it to `path`.
+///
+/// The original file is closed before renaming, which fails on Windows otherwise.
+fn rewrite(
+    path: &std::path::Path,
+    mut file: std::fs::File,
+    header_info: &HeaderInfo,
+    header: &Header,
+    data: &[u8],
+) -> Result<(), AppendError> {
+    let file_name: String = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let temp_path: std::path::PathBuf =
+        path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = (|| -> Result<(), AppendError> {
+        let mut temp = std::fs::File::create(&temp_path)?;
+        temp.set_permissions(file.metadata()?.permissions())?;
+        crate::write_header(&mut temp, header, &WriterOptions::default())?;
+        file.seek(SeekFrom::Start(header_info.data_offset as u64))?;
+        std::io::copy(
+            &mut (&mut file).take(header_info.payload_size as u64),
+            &mut temp,
+        )?;
+        temp.write_all(data)?;
+        temp.sync_all()?;
+        drop(temp);
+        drop(file);
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_rows;
+    use crate::error::AppendError;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rust_npy_io_{}_{}", std::process::id(), name))
+    }
+
+    fn load<T: crate::Element>(path: &std::path::Path) -> (crate::HeaderInfo, Vec<T>) {
+        let mut file = std::fs::File::open(path).unwrap();
+        let info = crate::validate_payload(&mut file).unwrap();
+        let data: Vec<T> = crate::read_array::<T>(&mut file, &info.header).unwrap();
+        (info, data)
+    }
+
+    #[test]
+    fn append_in_place() {
+        for fortran_order in [false, true] {
+            let path = temp_file(&format!("append_{}.npy", fortran_order));
+            let mut file = std::fs::File::create(&path).unwrap();
+            crate::write_array(&mut file, &[1usize, 1usize], fortran_order, &[0i64]).unwrap();
+            drop(file);
+            for n in 1i64..12i64 {
+                append_rows(&path, &[n]).unwrap();
+            }
+            let header = append_rows(&path, &[] as &[i64]).unwrap();
+            let shape: Vec<usize> = if fortran_order {
+                vec![1, 12]
+            } else {
+                vec![12, 1]
+            };
+            assert_eq!(header.shape, shape);
+            let (info, data) = load::<i64>(&path);
+            assert_eq!(info.data_offset, 128usize);
+            assert_eq!(data, (0i64..12i64).collect::<Vec<i64>>());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn append_with_rewrite() {
+        let path = temp_file("append_rewrite.npy");
+        // a compact header without spare spaces, filling the first 64 bytes
+        let dict: &str = "{'descr':'<i4','fortran_order':False,'shape':(3,)}";
+        let dict: String = format!("{}{}", dict, " ".repeat(53usize - dict.len()));
+        let mut buf: Vec<u8> = b"\x93NUMPY\x01\x00\x36\x00".to_vec();
+        buf.extend(dict.as_bytes());
+        buf.push(b'\n');
+        buf.extend(
+            [1i32, 2i32, 3i32]
+                .iter()
+                .flat_map(|x: &i32| x.to_le_bytes()),
+        );
+        std::fs::write(&path, &buf).unwrap();
+        append_rows(&path, &[4i32, 5i32]).unwrap();
+        let (info, data) = load::<i32>(&path);
+        assert_eq!(info.header.shape, vec![5usize]);
+        assert_eq!(info.data_offset, 128usize);
+        assert_eq!(data, vec![1i32, 2i32, 3i32, 4i32, 5i32]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn append_after_interruption() {
+        let path = temp_file("append_interrupted.npy");
+        let mut file = std::fs::File::create(&path).unwrap();
+        crate::write_array(&mut file, &[2usize], false, &[1u16, 2u16]).unwrap();
+        // rows written without the header being updated
+        std::io::Write::write_all(&mut file, &[0xffu8; 6]).unwrap();
+        drop(file);
+        let mut file = std::fs::File::open(&path).unwrap();
+        assert!(matches!(
+            crate::validate_payload(&mut file),
+            Err(crate::error::ValidatePayloadError::TrailingBytes(
+                4u64, 10u64
+            ))
+        ));
+        drop(file);
+        append_rows(&path, &[3u16]).unwrap();
+        let (info, data) = load::<u16>(&path);
+        assert_eq!(info.header.shape, vec![3usize]);
+        assert_eq!(data, vec![1u16, 2u16, 3u16]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_rows() {
+        let path = temp_file("append_invalid.npy");
+        let mut file = std::fs::File::create(&path).unwrap();
+        crate::write_array(&mut file, &[2usize, 3usize], false, &[0f32; 6]).unwrap();
+        drop(file);
+        assert!(matches!(
+            append_rows(&path, &[0f32; 4]),
+            Err(AppendError::IncompleteRow(4usize, 3usize))
+        ));
+        assert!(matches!(
+            append_rows(&path, &[0f64; 3]),
+            Err(AppendError::DTypeMismatch(..))
+        ));
+        let (info, _) = load::<f32>(&path);
+        assert_eq!(info.header.shape, vec![2usize, 3usize]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
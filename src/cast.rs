@@ -0,0 +1,247 @@
+//! Conversion between data types following NumPy's casting rules.
+
+#[cfg(feature = "reader")]
+use crate::dtype::ByteOrder;
+use crate::dtype::{DType, Kind};
+use crate::element::Element;
+
+/// How lossy a conversion may be, as the `casting` argument of `numpy.can_cast`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Casting {
+    /// Only conversions preserving every value, e.g. `<i4` to `f64`.
+    Safe,
+    /// Safe conversions and those within the same kind or to a higher one, e.g. `<f8` to `f32`.
+    SameKind,
+    /// Any conversion between numeric types, e.g. `<f8` to `i32`.
+    Unsafe,
+}
+
+impl std::fmt::Display for Casting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let name: &str = match self {
+            Casting::Safe => "safe",
+            Casting::SameKind => "same_kind",
+            Casting::Unsafe => "unsafe",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Order of the numeric kinds, where a cast to the same or a higher kind is allowed by `same_kind`.
+fn kind_rank(kind: Kind) -> Option<u8> {
+    match kind {
+        Kind::Bool => Some(0u8),
+        Kind::UInt => Some(1u8),
+        Kind::Int => Some(2u8),
+        Kind::Float => Some(3u8),
+        Kind::Complex => Some(4u8),
+        _ => None,
+    }
+}
+
+/// Whether every value of `from` is represented exactly by `to`,
+/// where 64-bit integers are regarded as safely cast to 64-bit floats as NumPy does.
+fn is_safe(from: &DType, to: &DType) -> bool {
+    let (from_size, to_size): (usize, usize) = (from.itemsize, to.itemsize);
+    match (from.kind, to.kind) {
+        (Kind::Bool, Kind::Bool | Kind::UInt | Kind::Int | Kind::Float | Kind::Complex) => true,
+        (Kind::UInt, Kind::UInt)
+        | (Kind::Int, Kind::Int)
+        | (Kind::Float, Kind::Float)
+        | (Kind::Complex, Kind::Complex) => from_size <= to_size,
+        (Kind::UInt, Kind::Int) => from_size < to_size,
+        (Kind::UInt | Kind::Int, Kind::Float) => {
+            from_size < to_size || (from_size == 8usize && to_size == 8usize)
+        }
+        (Kind::UInt | Kind::Int, Kind::Complex) => {
+            from_size < to_size / 2usize || (from_size == 8usize && to_size == 16usize)
+        }
+        (Kind::Float, Kind::Complex) => from_size <= to_size / 2usize,
+        _ => false,
+    }
+}
+
+/// Whether `from` can be converted to `to` under the given rule, as `numpy.can_cast` tells.
+///
+/// The byte order does not matter, as it is always converted losslessly.
+pub fn can_cast(from: &DType, to: &DType, casting: Casting) -> bool {
+    if from.kind == to.kind && from.itemsize == to.itemsize && from.time_unit == to.time_unit {
+        return true;
+    }
+    let ranks: Option<(u8, u8)> = kind_rank(from.kind).zip(kind_rank(to.kind));
+    match casting {
+        Casting::Safe => is_safe(from, to),
+        Casting::SameKind => {
+            is_safe(from, to) || ranks.is_some_and(|(from, to): (u8, u8)| from <= to)
+        }
+        Casting::Unsafe => ranks.is_some(),
+    }
+}
+
+/// An element value widened to the largest type of its kind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Complex(f64, f64),
+}
+
+/// An [`Element`] which can be converted from any numeric value,
+/// with the semantics of NumPy's (and C's) unsafe casts:
+/// integers wrap around, floats are rounded (or saturated) and nonzero values are true.
+pub trait Castable: Element {
+    fn from_scalar(scalar: Scalar) -> Self;
+}
+
+macro_rules! impl_castable {
+    ($type:ty) => {
+        impl Castable for $type {
+            fn from_scalar(scalar: Scalar) -> Self {
+                match scalar {
+                    Scalar::Bool(value) => u8::from(value) as $type,
+                    Scalar::Int(value) => value as $type,
+                    Scalar::UInt(value) => value as $type,
+                    Scalar::Float(value) => value as $type,
+                    // the imaginary part is discarded as NumPy does
+                    Scalar::Complex(re, _) => re as $type,
+                }
+            }
+        }
+    };
+}
+
+impl_castable!(i8);
+impl_castable!(i16);
+impl_castable!(i32);
+impl_castable!(i64);
+impl_castable!(u8);
+impl_castable!(u16);
+impl_castable!(u32);
+impl_castable!(u64);
+impl_castable!(f32);
+impl_castable!(f64);
+
+impl Castable for bool {
+    fn from_scalar(scalar: Scalar) -> Self {
+        match scalar {
+            Scalar::Bool(value) => value,
+            Scalar::Int(value) => value != 0i64,
+            Scalar::UInt(value) => value != 0u64,
+            Scalar::Float(value) => value != 0f64,
+            Scalar::Complex(re, im) => re != 0f64 || im != 0f64,
+        }
+    }
+}
+
+macro_rules! impl_castable_complex {
+    ($type:ty, $real:ty, $new:expr) => {
+        impl Castable for $type {
+            fn from_scalar(scalar: Scalar) -> Self {
+                let (re, im): ($real, $real) = match scalar {
+                    Scalar::Bool(value) => (u8::from(value) as $real, 0 as $real),
+                    Scalar::Int(value) => (value as $real, 0 as $real),
+                    Scalar::UInt(value) => (value as $real, 0 as $real),
+                    Scalar::Float(value) => (value as $real, 0 as $real),
+                    Scalar::Complex(re, im) => (re as $real, im as $real),
+                };
+                $new(re, im)
+            }
+        }
+    };
+}
+
+impl_castable_complex!([f32; 2], f32, |re: f32, im: f32| [re, im]);
+impl_castable_complex!([f64; 2], f64, |re: f64, im: f64| [re, im]);
+#[cfg(feature = "num-complex")]
+impl_castable_complex!(num_complex::Complex<f32>, f32, num_complex::Complex::new);
+#[cfg(feature = "num-complex")]
+impl_castable_complex!(num_complex::Complex<f64>, f64, num_complex::Complex::new);
+
+/// Converts IEEE 754 half precision, which has no counterpart among the Rust primitives.
+#[cfg(feature = "reader")]
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign: f64 = if bits & 0x8000u16 == 0u16 {
+        1f64
+    } else {
+        -1f64
+    };
+    let exponent: i32 = ((bits >> 10) & 0x1fu16) as i32;
+    let fraction: f64 = (bits & 0x3ffu16) as f64;
+    match exponent {
+        0i32 => sign * fraction * 2f64.powi(-24i32),
+        0x1fi32 if fraction == 0f64 => sign * f64::INFINITY,
+        0x1fi32 => f64::NAN,
+        _ => sign * (1f64 + fraction / 1024f64) * 2f64.powi(exponent - 15i32),
+    }
+}
+
+#[cfg(feature = "reader")]
+pub(crate) type Decoder = fn(&[u8], ByteOrder) -> Scalar;
+
+/// Decoder of the elements of the data type, which is `None` for non-numeric types
+/// and for extended precision floats.
+#[cfg(feature = "reader")]
+pub(crate) fn decoder_of(dtype: &DType) -> Option<Decoder> {
+    let decoder: Decoder = match (dtype.kind, dtype.itemsize) {
+        (Kind::Bool, 1usize) => |bytes, order| Scalar::Bool(bool::from_bytes(bytes, order)),
+        (Kind::Int, 1usize) => |bytes, order| Scalar::Int(i8::from_bytes(bytes, order) as i64),
+        (Kind::Int, 2usize) => |bytes, order| Scalar::Int(i16::from_bytes(bytes, order) as i64),
+        (Kind::Int, 4usize) => |bytes, order| Scalar::Int(i32::from_bytes(bytes, order) as i64),
+        (Kind::Int, 8usize) => |bytes, order| Scalar::Int(i64::from_bytes(bytes, order)),
+        (Kind::UInt, 1usize) => |bytes, order| Scalar::UInt(u8::from_bytes(bytes, order) as u64),
+        (Kind::UInt, 2usize) => |bytes, order| Scalar::UInt(u16::from_bytes(bytes, order) as u64),
+        (Kind::UInt, 4usize) => |bytes, order| Scalar::UInt(u32::from_bytes(bytes, order) as u64),
+        (Kind::UInt, 8usize) => |bytes, order| Scalar::UInt(u64::from_bytes(bytes, order)),
+        (Kind::Float, 2usize) => {
+            |bytes, order| Scalar::Float(f16_to_f64(u16::from_bytes(bytes, order)))
+        }
+        (Kind::Float, 4usize) => |bytes, order| Scalar::Float(f32::from_bytes(bytes, order) as f64),
+        (Kind::Float, 8usize) => |bytes, order| Scalar::Float(f64::from_bytes(bytes, order)),
+        (Kind::Complex, 8usize) => |bytes, order| {
+            let [re, im]: [f32; 2] = <[f32; 2]>::from_bytes(bytes, order);
+            Scalar::Complex(re as f64, im as f64)
+        },
+        (Kind::Complex, 16usize) => |bytes, order| {
+            let [re, im]: [f64; 2] = <[f64; 2]>::from_bytes(bytes, order);
+            Scalar::Complex(re, im)
+        },
+        _ => return None,
+    };
+    Some(decoder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_cast, Casting};
+    use crate::dtype::DType;
+
+    fn check(from: &str, to: &str) -> [bool; 3] {
+        let from: DType = from.parse().unwrap();
+        let to: DType = to.parse().unwrap();
+        [Casting::Safe, Casting::SameKind, Casting::Unsafe]
+            .map(|casting: Casting| can_cast(&from, &to, casting))
+    }
+
+    #[test]
+    fn numpy_rules() {
+        // compared with numpy.can_cast
+        assert_eq!(check(">f8", "<f8"), [true, true, true]);
+        assert_eq!(check("<i4", "<f8"), [true, true, true]);
+        assert_eq!(check("<i4", "<f4"), [false, true, true]);
+        assert_eq!(check("<i8", "<f8"), [true, true, true]);
+        assert_eq!(check("<u8", "<i8"), [false, true, true]);
+        assert_eq!(check("<i1", "<u8"), [false, false, true]);
+        assert_eq!(check("<u2", "<i4"), [true, true, true]);
+        assert_eq!(check("<f8", "<f4"), [false, true, true]);
+        assert_eq!(check("<f4", "<i8"), [false, false, true]);
+        assert_eq!(check("<f2", "<f4"), [true, true, true]);
+        assert_eq!(check("|b1", "<i1"), [true, true, true]);
+        assert_eq!(check("<i1", "|b1"), [false, false, true]);
+        assert_eq!(check("<f4", "<c8"), [true, true, true]);
+        assert_eq!(check("<i4", "<c8"), [false, true, true]);
+        assert_eq!(check("<c16", "<f8"), [false, false, true]);
+        assert_eq!(check("|S4", "<i4"), [false, false, false]);
+    }
+}
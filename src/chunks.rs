@@ -0,0 +1,203 @@
+//! Bounded-memory iteration over the outermost axis of arrays.
+
+use crate::dtype::ByteOrder;
+use crate::element::{self, Element};
+use crate::error::ReadArrayError;
+use crate::{reader, Header};
+
+/// A slab of the outermost axis.
+#[derive(Debug, PartialEq)]
+pub struct Chunk<'a, T> {
+    /// Range of the outermost axis covered by the chunk.
+    pub rows: std::ops::Range<usize>,
+    /// Elements in the order stored in the file.
+    pub data: &'a [T],
+}
+
+/// Reads the payload as consecutive slabs of the outermost axis,
+/// i.e. the first axis in C order and the last axis in Fortran order,
+/// each of which is contiguous in the file.
+///
+/// Only one slab is held in memory at a time, and its buffers are reused between slabs.
+pub struct ChunkReader<R: std::io::Read, T: Element> {
+    inner: R,
+    header: Header,
+    byte_order: ByteOrder,
+    /// Number of elements in one index of the outermost axis.
+    row_len: usize,
+    rows: usize,
+    rows_per_chunk: usize,
+    position: usize,
+    bytes: Vec<u8>,
+    data: Vec<T>,
+}
+
+impl<R: std::io::Read, T: Element> ChunkReader<R, T> {
+    /// Prepares reading `rows_per_chunk` indices of the outermost axis at a time.
+    ///
+    /// `inner` should be positioned at the beginning of the payload, e.g. by [`crate::read_header`].
+    /// A zero-dimensional array is treated as a single row of one element.
+    pub fn new(inner: R, header: Header, rows_per_chunk: usize) -> Result<Self, ReadArrayError> {
+        let byte_order = element::byte_order_of::<T>(&header.descr)
+            .ok_or_else(|| ReadArrayError::DTypeMismatch(header.descr.clone(), T::dtype()))?;
+        if rows_per_chunk == 0usize {
+            return Err(ReadArrayError::ZeroChunkSize);
+        }
+        let (rows, inner_axes): (usize, &[usize]) = match header.shape.len() {
+            0usize => (1usize, &[]),
+            ndim if header.fortran_order => (header.shape[ndim - 1], &header.shape[..ndim - 1]),
+            _ => (header.shape[0], &header.shape[1..]),
+        };
+        let row_len: Option<usize> = inner_axes
+            .iter()
+            .try_fold(1usize, |count: usize, &n: &usize| count.checked_mul(n));
+        // the whole payload may be larger than memory, but one chunk should be addressable
+        let chunk_size: Option<usize> = row_len.and_then(|row_len: usize| {
+            row_len
+                .checked_mul(T::dtype().itemsize)?
+                .checked_mul(rows_per_chunk.min(rows))
+        });
+        let row_len: usize = match (row_len, chunk_size) {
+            (Some(row_len), Some(_)) => row_len,
+            _ => return Err(ReadArrayError::SizeOverflow(header.shape.clone())),
+        };
+        Ok(ChunkReader {
+            inner,
+            header,
+            byte_order,
+            row_len,
+            rows,
+            rows_per_chunk,
+            position: 0usize,
+            bytes: Vec::new(),
+            data: Vec::new(),
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Number of indices of the outermost axis.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Shape of a chunk holding `rows` indices of the outermost axis.
+    pub fn chunk_shape(&self, rows: usize) -> Vec<usize> {
+        let mut shape: Vec<usize> = self.header.shape.clone();
+        match shape.len() {
+            0usize => {}
+            ndim if self.header.fortran_order => shape[ndim - 1] = rows,
+            _ => shape[0] = rows,
+        }
+        shape
+    }
+
+    /// Reads the next chunk, whose last one may hold fewer rows,
+    /// and `None` is returned after it.
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk<'_, T>>, ReadArrayError> {
+        if self.rows <= self.position {
+            return Ok(None);
+        }
+        let start: usize = self.position;
+        let end: usize = self.rows.min(start.saturating_add(self.rows_per_chunk));
+        let size: usize = (end - start) * self.row_len * T::dtype().itemsize;
+        reader::read_payload_into(&mut self.inner, size, &mut self.bytes)?;
+        element::decode_into(&self.bytes, self.byte_order, &mut self.data);
+        self.position = end;
+        Ok(Some(Chunk {
+            rows: start..end,
+            data: &self.data,
+        }))
+    }
+
+    /// Returns the underlying reader, positioned after the last chunk read.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(all(test, feature = "writer"))]
+mod tests {
+    use super::ChunkReader;
+    use crate::error::ReadArrayError;
+    use std::io::Cursor;
+
+    fn reader(
+        shape: &[usize],
+        fortran_order: bool,
+        rows: usize,
+    ) -> ChunkReader<Cursor<Vec<u8>>, i16> {
+        let count: usize = shape.iter().product();
+        let data: Vec<i16> = (0i16..count as i16).collect();
+        let mut buf = Vec::<u8>::new();
+        crate::write_array(&mut buf, shape, fortran_order, &data).unwrap();
+        let mut f = Cursor::new(buf);
+        let header = crate::read_header(&mut f).unwrap();
+        ChunkReader::new(f, header, rows).unwrap()
+    }
+
+    #[test]
+    fn outermost_axis() {
+        for fortran_order in [false, true] {
+            let shape: [usize; 3] = if fortran_order { [3, 2, 5] } else { [5, 3, 2] };
+            let mut chunks = reader(&shape, fortran_order, 2usize);
+            assert_eq!(chunks.rows(), 5usize);
+            let mut expected = 0i16..30i16;
+            let mut ranges = Vec::new();
+            while let Some(chunk) = chunks.next_chunk().unwrap() {
+                assert_eq!(chunk.data.len(), chunk.rows.len() * 6usize);
+                assert!(chunk
+                    .data
+                    .iter()
+                    .copied()
+                    .eq(expected.by_ref().take(chunk.data.len())));
+                ranges.push(chunk.rows);
+            }
+            assert_eq!(ranges, vec![0usize..2usize, 2usize..4usize, 4usize..5usize]);
+            assert!(chunks.next_chunk().unwrap().is_none());
+            let last: Vec<usize> = if fortran_order {
+                vec![3, 2, 1]
+            } else {
+                vec![1, 3, 2]
+            };
+            assert_eq!(chunks.chunk_shape(1usize), last);
+        }
+    }
+
+    #[test]
+    fn degenerate_shapes() {
+        let mut chunks = reader(&[], false, 4usize);
+        assert_eq!(chunks.next_chunk().unwrap().unwrap().data, &[0i16]);
+        assert!(chunks.next_chunk().unwrap().is_none());
+        let mut chunks = reader(&[0usize, 3usize], false, 4usize);
+        assert!(chunks.next_chunk().unwrap().is_none());
+        let mut chunks = reader(&[3usize, 0usize], false, 2usize);
+        assert_eq!(chunks.next_chunk().unwrap().unwrap().rows, 0usize..2usize);
+        assert_eq!(chunks.next_chunk().unwrap().unwrap().data, &[] as &[i16]);
+    }
+
+    #[test]
+    fn invalid_chunks() {
+        let mut buf = Vec::<u8>::new();
+        crate::write_array(&mut buf, &[4usize, 2usize], false, &[0f32; 8]).unwrap();
+        buf.truncate(buf.len() - 4usize);
+        let mut f = Cursor::new(buf);
+        let header = crate::read_header(&mut f).unwrap();
+        assert!(matches!(
+            ChunkReader::<_, f64>::new(&mut f, header.clone(), 1usize),
+            Err(ReadArrayError::DTypeMismatch(..))
+        ));
+        assert!(matches!(
+            ChunkReader::<_, f32>::new(&mut f, header.clone(), 0usize),
+            Err(ReadArrayError::ZeroChunkSize)
+        ));
+        let mut chunks = ChunkReader::<_, f32>::new(&mut f, header, 3usize).unwrap();
+        assert!(chunks.next_chunk().unwrap().is_some());
+        assert!(matches!(
+            chunks.next_chunk(),
+            Err(ReadArrayError::Truncated(8usize, 4usize))
+        ));
+    }
+}
@@ -7,6 +7,7 @@ pub const MINOR_VERSION: u8 = 0u8;
 pub const SIZE_HEADER_LEN: [usize; 2] = [2usize, 4usize];
 pub const HEADER_BLOCK_SIZE: usize = 64usize;
 #[cfg(feature = "writer")]
-pub const MAX_HEADER_SIZE_V1: usize = 65535usize;
+pub const MAX_HEADER_LEN_V1: usize = 65535usize;
+// spare spaces so that the growing axis of shape can be rewritten in-place
 #[cfg(feature = "writer")]
-pub const ENDIAN_SPECIFIERS: &[char] = &['<', '>', '=', '|'];
+pub const GROWTH_AXIS_MAX_DIGITS: usize = 21usize;
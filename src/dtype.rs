@@ -0,0 +1,557 @@
+//! Typed model of NumPy's `dtype.str`, e.g. `<f8`, `|b1`, `<U10` or `<M8[ns]`,
+//! and of the list-of-tuples form `dtype.descr` takes for structured types.
+
+use crate::error::DTypeError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+    /// For types whose byte order is irrelevant, e.g. `|u1` or `|S5`.
+    NotApplicable,
+}
+
+impl ByteOrder {
+    pub fn native() -> Self {
+        if cfg!(target_endian = "little") {
+            ByteOrder::Little
+        } else {
+            ByteOrder::Big
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            ByteOrder::Little => '<',
+            ByteOrder::Big => '>',
+            ByteOrder::NotApplicable => '|',
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    Int,
+    UInt,
+    Float,
+    Complex,
+    TimeDelta,
+    DateTime,
+    Bytes,
+    Unicode,
+    Void,
+    Object,
+}
+
+impl Kind {
+    pub fn to_char(self) -> char {
+        match self {
+            Kind::Bool => 'b',
+            Kind::Int => 'i',
+            Kind::UInt => 'u',
+            Kind::Float => 'f',
+            Kind::Complex => 'c',
+            Kind::TimeDelta => 'm',
+            Kind::DateTime => 'M',
+            Kind::Bytes => 'S',
+            Kind::Unicode => 'U',
+            Kind::Void => 'V',
+            Kind::Object => 'O',
+        }
+    }
+
+    pub fn from_char(character: char) -> Option<Self> {
+        let kind = match character {
+            'b' | '?' => Kind::Bool,
+            'i' => Kind::Int,
+            'u' => Kind::UInt,
+            'f' => Kind::Float,
+            'c' => Kind::Complex,
+            'm' => Kind::TimeDelta,
+            'M' => Kind::DateTime,
+            'S' | 'a' => Kind::Bytes,
+            'U' => Kind::Unicode,
+            'V' => Kind::Void,
+            'O' => Kind::Object,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    fn is_valid_itemsize(self, itemsize: usize) -> bool {
+        match self {
+            Kind::Bool => itemsize == 1usize,
+            Kind::Int | Kind::UInt => matches!(itemsize, 1usize | 2usize | 4usize | 8usize),
+            Kind::Float => matches!(itemsize, 2usize | 4usize | 8usize | 12usize | 16usize),
+            Kind::Complex => matches!(itemsize, 8usize | 16usize | 24usize | 32usize),
+            Kind::TimeDelta | Kind::DateTime | Kind::Object => itemsize == 8usize,
+            Kind::Unicode => itemsize.is_multiple_of(4usize),
+            Kind::Bytes | Kind::Void => true,
+        }
+    }
+}
+
+/// A simple (non-structured) NumPy data type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DType {
+    pub byte_order: ByteOrder,
+    pub kind: Kind,
+    /// Size of one element in bytes (not in characters for `U`).
+    pub itemsize: usize,
+    /// Unit of `m` and `M` kinds, e.g. `ns` of `<M8[ns]`.
+    pub time_unit: Option<String>,
+}
+
+impl DType {
+    pub fn new(byte_order: ByteOrder, kind: Kind, itemsize: usize) -> Self {
+        DType {
+            byte_order,
+            kind,
+            itemsize,
+            time_unit: None,
+        }
+    }
+
+    /// Whether the byte order of this type matters at all.
+    pub fn has_byte_order(&self) -> bool {
+        match self.kind {
+            Kind::Bool | Kind::Bytes | Kind::Void | Kind::Object => false,
+            Kind::Unicode => true,
+            _ => self.itemsize > 1usize,
+        }
+    }
+
+    /// Whether the elements are stored in the byte order of the running machine.
+    pub fn is_native_endian(&self) -> bool {
+        !self.has_byte_order() || self.byte_order == ByteOrder::native()
+    }
+
+    /// Checks the consistency between the kind, the item size and the byte order.
+    pub fn validate(&self) -> Result<(), DTypeError> {
+        if !self.kind.is_valid_itemsize(self.itemsize) {
+            return Err(DTypeError::invalid_itemsize(&self.to_string()));
+        }
+        if self.has_byte_order() && self.byte_order == ByteOrder::NotApplicable {
+            return Err(DTypeError::missing_byte_order(&self.to_string()));
+        }
+        let is_time = matches!(self.kind, Kind::TimeDelta | Kind::DateTime);
+        if !is_time && self.time_unit.is_some() {
+            return Err(DTypeError::invalid_time_unit(&self.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DType {
+    /// Formats the type as NumPy's `dtype.str`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let byte_order: ByteOrder = if self.has_byte_order() {
+            self.byte_order
+        } else {
+            ByteOrder::NotApplicable
+        };
+        write!(f, "{}{}", byte_order.to_char(), self.kind.to_char())?;
+        match self.kind {
+            Kind::Object => {}
+            Kind::Unicode => write!(f, "{}", self.itemsize / 4usize)?,
+            _ => write!(f, "{}", self.itemsize)?,
+        }
+        if let Some(time_unit) = &self.time_unit {
+            write!(f, "[{}]", time_unit)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for DType {
+    type Err = DTypeError;
+
+    /// Parses NumPy's `dtype.str`, normalising the byte order as NumPy does.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut characters = string.chars().peekable();
+        let byte_order: Option<ByteOrder> = match characters.peek() {
+            None => return Err(DTypeError::Empty),
+            Some('<') => Some(ByteOrder::Little),
+            Some('>') => Some(ByteOrder::Big),
+            Some('=') => Some(ByteOrder::native()),
+            Some('|') => Some(ByteOrder::NotApplicable),
+            Some(_) => None,
+        };
+        if byte_order.is_some() {
+            characters.next();
+        }
+        let kind: Kind = characters
+            .next()
+            .and_then(Kind::from_char)
+            .ok_or_else(|| DTypeError::unknown_kind(string))?;
+        let rest: String = characters.collect();
+        let (size, time_unit): (&str, Option<String>) = match rest.split_once('[') {
+            Some((size, unit)) => match unit.strip_suffix(']') {
+                Some(unit) if !unit.is_empty() => (size, Some(unit.to_string())),
+                _ => return Err(DTypeError::invalid_time_unit(string)),
+            },
+            None => (rest.as_str(), None),
+        };
+        let itemsize: usize = match (kind, size) {
+            (Kind::Object, "") => 8usize,
+            (Kind::Unicode, size) => size
+                .parse::<usize>()
+                .ok()
+                .and_then(|nchars: usize| nchars.checked_mul(4usize))
+                .ok_or_else(|| DTypeError::invalid_itemsize(string))?,
+            (_, size) => size
+                .parse::<usize>()
+                .map_err(|_| DTypeError::invalid_itemsize(string))?,
+        };
+        let mut dtype = DType {
+            byte_order: ByteOrder::NotApplicable,
+            kind,
+            itemsize,
+            time_unit,
+        };
+        if dtype.has_byte_order() {
+            dtype.byte_order = match byte_order {
+                Some(ByteOrder::Little) => ByteOrder::Little,
+                Some(ByteOrder::Big) => ByteOrder::Big,
+                _ => ByteOrder::native(),
+            };
+        }
+        dtype.validate()?;
+        Ok(dtype)
+    }
+}
+
+/// A field of a structured data type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// Name of the field, which is empty for padding bytes.
+    pub name: String,
+    pub descr: Descr,
+    /// Shape of the sub-array, which is empty for scalar fields.
+    pub shape: Vec<usize>,
+}
+
+impl Field {
+    pub fn itemsize(&self) -> usize {
+        self.descr.itemsize() * self.shape.iter().product::<usize>()
+    }
+}
+
+/// The value of 'descr': either a simple type or a list of (possibly nested) fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Descr {
+    Simple(DType),
+    Record(Vec<Field>),
+}
+
+impl Descr {
+    /// Size of one element in bytes, including all fields and padding.
+    pub fn itemsize(&self) -> usize {
+        match self {
+            Descr::Simple(dtype) => dtype.itemsize,
+            Descr::Record(fields) => fields.iter().map(Field::itemsize).sum(),
+        }
+    }
+
+    /// Byte offsets of the fields from the beginning of an element.
+    ///
+    /// Fields are packed without gaps; padding is described by fields with empty names.
+    pub fn offsets(&self) -> Vec<usize> {
+        match self {
+            Descr::Simple(_) => Vec::new(),
+            Descr::Record(fields) => fields
+                .iter()
+                .scan(0usize, |offset: &mut usize, field: &Field| {
+                    let current: usize = *offset;
+                    *offset += field.itemsize();
+                    Some(current)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn as_simple(&self) -> Option<&DType> {
+        match self {
+            Descr::Simple(dtype) => Some(dtype),
+            Descr::Record(_) => None,
+        }
+    }
+
+    /// Checks all nested data types, the uniqueness of field names and the total size.
+    pub fn validate(&self) -> Result<(), DTypeError> {
+        let fields: &[Field] = match self {
+            Descr::Simple(dtype) => return dtype.validate(),
+            Descr::Record(fields) => fields,
+        };
+        for (n, field) in fields.iter().enumerate() {
+            field.descr.validate()?;
+            let is_duplicated = fields[..n]
+                .iter()
+                .any(|other: &Field| other.name == field.name);
+            if !field.name.is_empty() && is_duplicated {
+                return Err(DTypeError::duplicate_field_name(&field.name));
+            }
+        }
+        let itemsize: Option<usize> = fields.iter().try_fold(0usize, |sum, field| {
+            field
+                .shape
+                .iter()
+                .try_fold(field.descr.itemsize(), |size, &n| size.checked_mul(n))
+                .and_then(|size: usize| sum.checked_add(size))
+        });
+        if itemsize.is_none() {
+            return Err(DTypeError::invalid_itemsize(&self.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl From<DType> for Descr {
+    fn from(dtype: DType) -> Self {
+        Descr::Simple(dtype)
+    }
+}
+
+impl std::fmt::Display for Descr {
+    /// Formats the value as Python's `repr(dtype.descr)`, or `repr(dtype.str)` for simple types.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let fields: &[Field] = match self {
+            Descr::Simple(dtype) => return write!(f, "'{}'", dtype),
+            Descr::Record(fields) => fields,
+        };
+        write!(f, "[")?;
+        for (n, field) in fields.iter().enumerate() {
+            if n != 0usize {
+                write!(f, ", ")?;
+            }
+            write!(f, "({}, {}", repr_str(&field.name), field.descr)?;
+            match field.shape.as_slice() {
+                [] => {}
+                [length] => write!(f, ", ({},)", length)?,
+                shape => {
+                    let shape: Vec<String> = shape.iter().map(usize::to_string).collect();
+                    write!(f, ", ({})", shape.join(", "))?;
+                }
+            }
+            write!(f, ")")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Code points of the categories Zs (but the space), Zl, Zp, Cf and Co,
+/// which Python's `str.isprintable` rejects in addition to the control characters.
+const NON_PRINTABLE: &[(u32, u32)] = &[
+    (0x00a0, 0x00a0),
+    (0x00ad, 0x00ad),
+    (0x0600, 0x0605),
+    (0x061c, 0x061c),
+    (0x06dd, 0x06dd),
+    (0x070f, 0x070f),
+    (0x0890, 0x0891),
+    (0x08e2, 0x08e2),
+    (0x1680, 0x1680),
+    (0x180e, 0x180e),
+    (0x2000, 0x200f),
+    (0x2028, 0x202f),
+    (0x205f, 0x2064),
+    (0x2066, 0x206f),
+    (0x3000, 0x3000),
+    (0xe000, 0xf8ff),
+    (0xfeff, 0xfeff),
+    (0xfff9, 0xfffb),
+    (0x110bd, 0x110bd),
+    (0x110cd, 0x110cd),
+    (0x13430, 0x1343f),
+    (0x1bca0, 0x1bca3),
+    (0x1d173, 0x1d17a),
+    (0xe0001, 0xe0001),
+    (0xe0020, 0xe007f),
+    (0xf0000, 0xffffd),
+    (0x100000, 0x10fffd),
+];
+
+/// Follows Python's `str.isprintable`,
+/// except that unassigned code points, which depend on the Unicode version of Python, are printable.
+fn is_printable(character: char) -> bool {
+    let code: u32 = character as u32;
+    !character.is_control()
+        && !NON_PRINTABLE
+            .iter()
+            .any(|&(first, last): &(u32, u32)| first <= code && code <= last)
+}
+
+/// Quotes a string in the same way as Python's `repr`.
+pub(crate) fn repr_str(string: &str) -> String {
+    let quote: char = if string.contains('\'') && !string.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut repr = String::with_capacity(string.len() + 2);
+    repr.push(quote);
+    for character in string.chars() {
+        match character {
+            '\\' => repr.push_str(r"\\"),
+            '\n' => repr.push_str(r"\n"),
+            '\r' => repr.push_str(r"\r"),
+            '\t' => repr.push_str(r"\t"),
+            c if c == quote => {
+                repr.push('\\');
+                repr.push(c);
+            }
+            c if is_printable(c) => repr.push(c),
+            c if (c as u32) < 0x100u32 => repr.push_str(&format!(r"\x{:02x}", c as u32)),
+            c if (c as u32) < 0x10000u32 => repr.push_str(&format!(r"\u{:04x}", c as u32)),
+            c => repr.push_str(&format!(r"\U{:08x}", c as u32)),
+        }
+    }
+    repr.push(quote);
+    repr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{repr_str, ByteOrder, DType, Descr, Field, Kind};
+    use crate::error::DTypeError;
+
+    #[test]
+    fn parse_normal() {
+        assert_eq!(
+            "<f8".parse::<DType>(),
+            Ok(DType::new(ByteOrder::Little, Kind::Float, 8usize))
+        );
+        assert_eq!(
+            ">i4".parse::<DType>(),
+            Ok(DType::new(ByteOrder::Big, Kind::Int, 4usize))
+        );
+        assert_eq!(
+            "|b1".parse::<DType>(),
+            Ok(DType::new(ByteOrder::NotApplicable, Kind::Bool, 1usize))
+        );
+        assert_eq!(
+            "<u1".parse::<DType>(),
+            Ok(DType::new(ByteOrder::NotApplicable, Kind::UInt, 1usize))
+        );
+        assert_eq!(
+            "=c16".parse::<DType>(),
+            Ok(DType::new(ByteOrder::native(), Kind::Complex, 16usize))
+        );
+        assert_eq!(
+            "<U10".parse::<DType>(),
+            Ok(DType::new(ByteOrder::Little, Kind::Unicode, 40usize))
+        );
+        assert_eq!(
+            "|O".parse::<DType>(),
+            Ok(DType::new(ByteOrder::NotApplicable, Kind::Object, 8usize))
+        );
+        let mut datetime = DType::new(ByteOrder::Little, Kind::DateTime, 8usize);
+        datetime.time_unit = Some("ns".to_string());
+        assert_eq!("<M8[ns]".parse::<DType>(), Ok(datetime));
+    }
+
+    #[test]
+    fn format_normal() {
+        for string in [
+            "<f8",
+            ">f4",
+            "<f2",
+            "|b1",
+            "|i1",
+            "<i8",
+            ">u2",
+            "<c8",
+            "|S5",
+            "<U3",
+            "|V16",
+            "|O",
+            "<m8[s]",
+            ">M8[10us]",
+        ] {
+            assert_eq!(string.parse::<DType>().unwrap().to_string(), string);
+        }
+        // byte order of single-byte types is not applicable
+        let dtype = DType::new(ByteOrder::Little, Kind::UInt, 1usize);
+        assert_eq!(dtype.to_string(), "|u1");
+    }
+
+    #[test]
+    fn parse_corner() {
+        assert_eq!("".parse::<DType>(), Err(DTypeError::Empty));
+        assert_eq!("<".parse::<DType>(), Err(DTypeError::unknown_kind("<")));
+        assert_eq!("<x8".parse::<DType>(), Err(DTypeError::unknown_kind("<x8")));
+        for string in ["<f", "<f3", "<i16", "|b2", "<f8x", "<M4[ns]"] {
+            assert_eq!(
+                string.parse::<DType>(),
+                Err(DTypeError::invalid_itemsize(string))
+            );
+        }
+        for string in ["<M8[]", "<M8[ns", "<f8[ns]"] {
+            assert_eq!(
+                string.parse::<DType>(),
+                Err(DTypeError::invalid_time_unit(string))
+            );
+        }
+    }
+
+    #[test]
+    fn validate_corner() {
+        let dtype = DType::new(ByteOrder::NotApplicable, Kind::Float, 8usize);
+        assert_eq!(dtype.validate(), Err(DTypeError::missing_byte_order("|f8")));
+        let dtype = DType::new(ByteOrder::Little, Kind::Float, 3usize);
+        assert_eq!(dtype.validate(), Err(DTypeError::invalid_itemsize("<f3")));
+    }
+
+    fn record() -> Descr {
+        let field = |name: &str, descr: &str, shape: &[usize]| Field {
+            name: name.to_string(),
+            descr: Descr::Simple(descr.parse::<DType>().unwrap()),
+            shape: shape.to_vec(),
+        };
+        Descr::Record(vec![
+            field("id", "<i4", &[]),
+            field("", "|V4", &[]),
+            field("x", "<f8", &[3usize]),
+            Field {
+                name: "nested".to_string(),
+                descr: Descr::Record(vec![field("a", "|u1", &[2usize, 2usize])]),
+                shape: vec![],
+            },
+        ])
+    }
+
+    #[test]
+    fn record_layout() {
+        let descr = record();
+        assert_eq!(descr.itemsize(), 4 + 4 + 24 + 4);
+        assert_eq!(descr.offsets(), vec![0usize, 4usize, 8usize, 32usize]);
+        assert_eq!(descr.validate(), Ok(()));
+        assert_eq!(
+            descr.to_string(),
+            "[('id', '<i4'), ('', '|V4'), ('x', '<f8', (3,)), ('nested', [('a', '|u1', (2, 2))])]"
+        );
+    }
+
+    #[test]
+    fn record_corner() {
+        let mut descr = record();
+        if let Descr::Record(fields) = &mut descr {
+            fields[2].name = "id".to_string();
+        }
+        assert_eq!(
+            descr.validate(),
+            Err(DTypeError::duplicate_field_name("id"))
+        );
+    }
+
+    #[test]
+    fn python_repr() {
+        // compared with repr in Python
+        assert_eq!(repr_str("it's"), r#""it's""#);
+        assert_eq!(
+            repr_str("a\u{a0}b\u{2028}c\u{e0001}d\u{7f}é速\u{e000}"),
+            r"'a\xa0b\u2028c\U000e0001d\x7fé速\ue000'"
+        );
+    }
+}
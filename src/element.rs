@@ -0,0 +1,242 @@
+//! Rust types which can be stored as the elements of NPY arrays.
+
+#[cfg(feature = "reader")]
+use crate::dtype::Descr;
+use crate::dtype::{ByteOrder, DType, Kind};
+
+/// A fixed-size type with a one-to-one counterpart among NumPy's simple data types.
+pub trait Element: Copy {
+    /// The NumPy data type of `Self` in the native byte order.
+    fn dtype() -> DType;
+
+    /// Decodes one element from `dtype().itemsize` bytes stored in the given byte order.
+    fn from_bytes(bytes: &[u8], byte_order: ByteOrder) -> Self;
+
+    /// Encodes one element into `dtype().itemsize` bytes in the given byte order.
+    fn to_bytes(&self, bytes: &mut [u8], byte_order: ByteOrder);
+}
+
+/// An [`Element`] which is plain data accepting any bit pattern,
+/// and hence can be viewed in-place from raw bytes in the native byte order.
+///
+/// # Safety
+///
+/// Implementors must have no padding bytes and no invalid bit patterns (unlike `bool`),
+/// and their in-memory representation must be what `to_bytes` gives in the native byte order.
+pub unsafe trait Plain: Element {}
+
+fn native_dtype(kind: Kind, itemsize: usize) -> DType {
+    let mut dtype = DType::new(ByteOrder::native(), kind, itemsize);
+    if !dtype.has_byte_order() {
+        dtype.byte_order = ByteOrder::NotApplicable;
+    }
+    dtype
+}
+
+macro_rules! impl_element {
+    ($type:ty, $kind:expr) => {
+        impl Element for $type {
+            fn dtype() -> DType {
+                native_dtype($kind, std::mem::size_of::<$type>())
+            }
+
+            fn from_bytes(bytes: &[u8], byte_order: ByteOrder) -> Self {
+                let bytes: [u8; std::mem::size_of::<$type>()] = bytes.try_into().unwrap();
+                match byte_order {
+                    ByteOrder::Little => <$type>::from_le_bytes(bytes),
+                    ByteOrder::Big => <$type>::from_be_bytes(bytes),
+                    ByteOrder::NotApplicable => <$type>::from_ne_bytes(bytes),
+                }
+            }
+
+            fn to_bytes(&self, bytes: &mut [u8], byte_order: ByteOrder) {
+                let encoded: [u8; std::mem::size_of::<$type>()] = match byte_order {
+                    ByteOrder::Little => self.to_le_bytes(),
+                    ByteOrder::Big => self.to_be_bytes(),
+                    ByteOrder::NotApplicable => self.to_ne_bytes(),
+                };
+                bytes.copy_from_slice(&encoded);
+            }
+        }
+    };
+}
+
+impl_element!(i8, Kind::Int);
+impl_element!(i16, Kind::Int);
+impl_element!(i32, Kind::Int);
+impl_element!(i64, Kind::Int);
+impl_element!(u8, Kind::UInt);
+impl_element!(u16, Kind::UInt);
+impl_element!(u32, Kind::UInt);
+impl_element!(u64, Kind::UInt);
+impl_element!(f32, Kind::Float);
+impl_element!(f64, Kind::Float);
+
+unsafe impl Plain for i8 {}
+unsafe impl Plain for i16 {}
+unsafe impl Plain for i32 {}
+unsafe impl Plain for i64 {}
+unsafe impl Plain for u8 {}
+unsafe impl Plain for u16 {}
+unsafe impl Plain for u32 {}
+unsafe impl Plain for u64 {}
+unsafe impl Plain for f32 {}
+unsafe impl Plain for f64 {}
+
+/// Complex numbers stored as the real part followed by the imaginary part.
+macro_rules! impl_complex_element {
+    ($type:ty, $real:ty, $new:expr, $parts:expr) => {
+        impl Element for $type {
+            fn dtype() -> DType {
+                native_dtype(Kind::Complex, std::mem::size_of::<$type>())
+            }
+
+            fn from_bytes(bytes: &[u8], byte_order: ByteOrder) -> Self {
+                let (re, im): (&[u8], &[u8]) = bytes.split_at(std::mem::size_of::<$real>());
+                $new(
+                    <$real>::from_bytes(re, byte_order),
+                    <$real>::from_bytes(im, byte_order),
+                )
+            }
+
+            fn to_bytes(&self, bytes: &mut [u8], byte_order: ByteOrder) {
+                let (re, im): (&mut [u8], &mut [u8]) =
+                    bytes.split_at_mut(std::mem::size_of::<$real>());
+                let (x, y): ($real, $real) = $parts(self);
+                x.to_bytes(re, byte_order);
+                y.to_bytes(im, byte_order);
+            }
+        }
+
+        unsafe impl Plain for $type {}
+    };
+}
+
+impl_complex_element!(
+    [f32; 2],
+    f32,
+    |re: f32, im: f32| [re, im],
+    |z: &[f32; 2]| (z[0], z[1])
+);
+impl_complex_element!(
+    [f64; 2],
+    f64,
+    |re: f64, im: f64| [re, im],
+    |z: &[f64; 2]| (z[0], z[1])
+);
+#[cfg(feature = "num-complex")]
+impl_complex_element!(
+    num_complex::Complex<f32>,
+    f32,
+    num_complex::Complex::new,
+    |z: &num_complex::Complex<f32>| (z.re, z.im)
+);
+#[cfg(feature = "num-complex")]
+impl_complex_element!(
+    num_complex::Complex<f64>,
+    f64,
+    num_complex::Complex::new,
+    |z: &num_complex::Complex<f64>| (z.re, z.im)
+);
+
+impl Element for bool {
+    fn dtype() -> DType {
+        native_dtype(Kind::Bool, 1usize)
+    }
+
+    fn from_bytes(bytes: &[u8], _byte_order: ByteOrder) -> Self {
+        bytes[0] != 0u8
+    }
+
+    fn to_bytes(&self, bytes: &mut [u8], _byte_order: ByteOrder) {
+        bytes[0] = u8::from(*self);
+    }
+}
+
+/// Returns the byte order of the stored elements if `descr` describes `T`.
+#[cfg(feature = "reader")]
+pub(crate) fn byte_order_of<T: Element>(descr: &Descr) -> Option<ByteOrder> {
+    let expected: DType = T::dtype();
+    let dtype: &DType = descr.as_simple()?;
+    if dtype.kind != expected.kind || dtype.itemsize != expected.itemsize {
+        return None;
+    }
+    Some(dtype.byte_order)
+}
+
+#[cfg(feature = "reader")]
+pub(crate) fn decode<T: Element>(bytes: &[u8], byte_order: ByteOrder) -> Vec<T> {
+    let mut data = Vec::<T>::new();
+    decode_into(bytes, byte_order, &mut data);
+    data
+}
+
+#[cfg(feature = "reader")]
+pub(crate) fn decode_into<T: Element>(bytes: &[u8], byte_order: ByteOrder, data: &mut Vec<T>) {
+    let itemsize: usize = T::dtype().itemsize;
+    data.clear();
+    data.extend(
+        bytes
+            .chunks_exact(itemsize)
+            .map(|chunk: &[u8]| T::from_bytes(chunk, byte_order)),
+    );
+}
+
+#[cfg(feature = "writer")]
+pub(crate) fn encode<T: Element>(data: &[T], byte_order: ByteOrder, buf: &mut Vec<u8>) {
+    let itemsize: usize = T::dtype().itemsize;
+    let start: usize = buf.len();
+    buf.resize(start + itemsize * data.len(), 0u8);
+    for (datum, chunk) in data.iter().zip(buf[start..].chunks_exact_mut(itemsize)) {
+        datum.to_bytes(chunk, byte_order);
+    }
+}
+
+#[cfg(all(test, feature = "reader"))]
+mod tests {
+    use super::{byte_order_of, decode, Element};
+    use crate::dtype::{ByteOrder, DType, Descr, Kind};
+
+    #[test]
+    fn dtypes() {
+        assert_eq!(
+            f64::dtype(),
+            DType::new(ByteOrder::native(), Kind::Float, 8usize)
+        );
+        assert_eq!(u8::dtype().to_string(), "|u1");
+        assert_eq!(i8::dtype().to_string(), "|i1");
+        assert_eq!(bool::dtype().to_string(), "|b1");
+        assert_eq!(<[f32; 2]>::dtype().kind, Kind::Complex);
+        assert_eq!(<[f64; 2]>::dtype().itemsize, 16usize);
+    }
+
+    #[test]
+    fn byte_orders() {
+        let data: [u32; 2] = [0x01020304u32, 0x0a0b0c0du32];
+        let buf: [u8; 8] = [1, 2, 3, 4, 10, 11, 12, 13];
+        assert_eq!(decode::<u32>(&buf, ByteOrder::Big), data);
+        let buf: [u8; 8] = [4, 3, 2, 1, 13, 12, 11, 10];
+        assert_eq!(decode::<u32>(&buf, ByteOrder::Little), data);
+        assert_eq!(
+            decode::<bool>(&[0u8, 1u8, 2u8], ByteOrder::NotApplicable),
+            [false, true, true]
+        );
+        // each part is swapped in place
+        let buf: [u8; 8] = [0x3f, 0x80, 0, 0, 0xc0, 0, 0, 0];
+        assert_eq!(decode::<[f32; 2]>(&buf, ByteOrder::Big), [[1f32, -2f32]]);
+    }
+
+    #[test]
+    fn matching() {
+        let descr = |s: &str| Descr::Simple(s.parse::<DType>().unwrap());
+        assert_eq!(byte_order_of::<f64>(&descr(">f8")), Some(ByteOrder::Big));
+        assert_eq!(byte_order_of::<f64>(&descr("<f8")), Some(ByteOrder::Little));
+        assert_eq!(
+            byte_order_of::<u8>(&descr("|u1")),
+            Some(ByteOrder::NotApplicable)
+        );
+        assert_eq!(byte_order_of::<f64>(&descr("<i8")), None);
+        assert_eq!(byte_order_of::<f32>(&descr("<f8")), None);
+        assert_eq!(byte_order_of::<bool>(&descr("|u1")), None);
+    }
+}
@@ -1,8 +1,94 @@
 #[cfg(feature = "reader")]
+use crate::dtype::{DType, Descr};
+#[cfg(feature = "reader")]
 use crate::reader::error::ParseError;
 #[cfg(feature = "writer")]
 use crate::writer::error::ValidationError;
 
+#[derive(Debug, PartialEq)]
+pub enum DTypeError {
+    Empty,
+    UnknownKind(String),
+    InvalidItemsize(String),
+    MissingByteOrder(String),
+    InvalidTimeUnit(String),
+    DuplicateFieldName(String),
+}
+
+impl std::error::Error for DTypeError {}
+
+impl std::fmt::Display for DTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            DTypeError::Empty => {
+                write!(f, "Data type is empty")
+            }
+            DTypeError::UnknownKind(value) => {
+                write!(f, "Unknown kind of data type: '{}'", value)
+            }
+            DTypeError::InvalidItemsize(value) => {
+                write!(f, "Invalid item size for the kind: '{}'", value)
+            }
+            DTypeError::MissingByteOrder(value) => {
+                write!(f, "Byte order is not specified: '{}'", value)
+            }
+            DTypeError::InvalidTimeUnit(value) => {
+                write!(f, "Invalid time unit: '{}'", value)
+            }
+            DTypeError::DuplicateFieldName(name) => {
+                write!(f, "Field name is duplicated: '{}'", name)
+            }
+        }
+    }
+}
+
+impl DTypeError {
+    pub fn unknown_kind(value: &str) -> Self {
+        DTypeError::UnknownKind(value.to_string())
+    }
+
+    pub fn invalid_itemsize(value: &str) -> Self {
+        DTypeError::InvalidItemsize(value.to_string())
+    }
+
+    pub fn missing_byte_order(value: &str) -> Self {
+        DTypeError::MissingByteOrder(value.to_string())
+    }
+
+    pub fn invalid_time_unit(value: &str) -> Self {
+        DTypeError::InvalidTimeUnit(value.to_string())
+    }
+
+    pub fn duplicate_field_name(name: &str) -> Self {
+        DTypeError::DuplicateFieldName(name.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    SizeOverflow(Vec<usize>),
+    ShapeMismatch(Vec<usize>, usize),
+}
+
+impl std::error::Error for LayoutError {}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            LayoutError::SizeOverflow(shape) => {
+                write!(f, "Size overflows for shape: {:?}", shape)
+            }
+            LayoutError::ShapeMismatch(shape, len) => {
+                write!(
+                    f,
+                    "Shape {:?} does not match the number of elements: {}",
+                    shape, len
+                )
+            }
+        }
+    }
+}
+
 #[cfg(feature = "reader")]
 #[derive(Debug)]
 pub enum ReadHeaderError {
@@ -12,6 +98,7 @@ pub enum ReadHeaderError {
     InvalidMinorVersion(u8),
     InvalidHeaderSize(usize),
     ParseFailed(ParseError),
+    SizeOverflow(Vec<usize>),
 }
 
 #[cfg(feature = "reader")]
@@ -44,6 +131,9 @@ impl std::fmt::Display for ReadHeaderError {
             ReadHeaderError::ParseFailed(error) => {
                 write!(f, "Failed to parse dictionary: {}", error)
             }
+            ReadHeaderError::SizeOverflow(shape) => {
+                write!(f, "Payload size overflows for shape: {:?}", shape)
+            }
         }
     }
 }
@@ -62,12 +152,332 @@ impl From<ParseError> for ReadHeaderError {
     }
 }
 
+#[cfg(feature = "reader")]
+#[derive(Debug)]
+pub enum ReadArrayError {
+    Io(std::io::Error),
+    DTypeMismatch(Descr, DType),
+    SizeOverflow(Vec<usize>),
+    Truncated(usize, usize),
+    ZeroChunkSize,
+    InvalidCast(Descr, DType, crate::cast::Casting),
+}
+
+#[cfg(feature = "reader")]
+impl std::error::Error for ReadArrayError {}
+
+#[cfg(feature = "reader")]
+impl std::fmt::Display for ReadArrayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ReadArrayError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            ReadArrayError::DTypeMismatch(descr, dtype) => {
+                write!(
+                    f,
+                    "Data type in the header {} does not match the requested type '{}'",
+                    descr, dtype
+                )
+            }
+            ReadArrayError::SizeOverflow(shape) => {
+                write!(f, "Payload size overflows for shape: {:?}", shape)
+            }
+            ReadArrayError::Truncated(expected, actual) => {
+                write!(
+                    f,
+                    "Payload is truncated: {} bytes are expected but {} bytes are found",
+                    expected, actual
+                )
+            }
+            ReadArrayError::ZeroChunkSize => {
+                write!(f, "Number of rows per chunk should be positive")
+            }
+            ReadArrayError::InvalidCast(descr, dtype, casting) => {
+                write!(
+                    f,
+                    "Data type in the header {} cannot be cast to '{}' under the rule '{}'",
+                    descr, dtype, casting
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "reader")]
+impl From<std::io::Error> for ReadArrayError {
+    fn from(error: std::io::Error) -> Self {
+        ReadArrayError::Io(error)
+    }
+}
+
+#[cfg(feature = "reader")]
+#[derive(Debug)]
+pub enum ValidatePayloadError {
+    Io(std::io::Error),
+    ReadHeaderFailed(ReadHeaderError),
+    SizeOverflow(Vec<usize>),
+    Truncated(u64, u64),
+    TrailingBytes(u64, u64),
+}
+
+#[cfg(feature = "reader")]
+impl std::error::Error for ValidatePayloadError {}
+
+#[cfg(feature = "reader")]
+impl std::fmt::Display for ValidatePayloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ValidatePayloadError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            ValidatePayloadError::ReadHeaderFailed(error) => {
+                write!(f, "Failed to read header: {}", error)
+            }
+            ValidatePayloadError::SizeOverflow(shape) => {
+                write!(f, "Payload size overflows for shape: {:?}", shape)
+            }
+            ValidatePayloadError::Truncated(expected, actual) => {
+                write!(
+                    f,
+                    "Payload is truncated: {} bytes are expected but {} bytes are found",
+                    expected, actual
+                )
+            }
+            ValidatePayloadError::TrailingBytes(expected, actual) => {
+                write!(
+                    f,
+                    "Trailing bytes follow the payload: {} bytes are expected but {} bytes are found",
+                    expected, actual
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "reader")]
+impl From<std::io::Error> for ValidatePayloadError {
+    fn from(error: std::io::Error) -> Self {
+        ValidatePayloadError::Io(error)
+    }
+}
+
+#[cfg(feature = "reader")]
+impl From<ReadHeaderError> for ValidatePayloadError {
+    fn from(error: ReadHeaderError) -> Self {
+        match error {
+            ReadHeaderError::SizeOverflow(shape) => ValidatePayloadError::SizeOverflow(shape),
+            error => ValidatePayloadError::ReadHeaderFailed(error),
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub enum MmapError {
+    Io(std::io::Error),
+    ValidatePayloadFailed(ValidatePayloadError),
+    DTypeMismatch(Descr, DType),
+    NonNativeByteOrder(DType),
+    Misaligned(usize, usize),
+    #[cfg(feature = "writer")]
+    WriteHeaderFailed(WriteHeaderError),
+}
+
+#[cfg(feature = "mmap")]
+impl std::error::Error for MmapError {}
+
+#[cfg(feature = "mmap")]
+impl std::fmt::Display for MmapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            MmapError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            MmapError::ValidatePayloadFailed(error) => {
+                write!(f, "Invalid file: {}", error)
+            }
+            MmapError::DTypeMismatch(descr, dtype) => {
+                write!(
+                    f,
+                    "Data type in the header {} does not match the requested type '{}'",
+                    descr, dtype
+                )
+            }
+            MmapError::NonNativeByteOrder(dtype) => {
+                write!(
+                    f,
+                    "Data type '{}' is not in the native byte order and cannot be viewed in-place",
+                    dtype
+                )
+            }
+            MmapError::Misaligned(offset, alignment) => {
+                write!(
+                    f,
+                    "Payload at offset {} is not aligned to {} bytes",
+                    offset, alignment
+                )
+            }
+            #[cfg(feature = "writer")]
+            MmapError::WriteHeaderFailed(error) => {
+                write!(f, "Failed to write header: {}", error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl From<std::io::Error> for MmapError {
+    fn from(error: std::io::Error) -> Self {
+        MmapError::Io(error)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl From<ValidatePayloadError> for MmapError {
+    fn from(error: ValidatePayloadError) -> Self {
+        MmapError::ValidatePayloadFailed(error)
+    }
+}
+
+#[cfg(all(feature = "mmap", feature = "writer"))]
+impl From<WriteHeaderError> for MmapError {
+    fn from(error: WriteHeaderError) -> Self {
+        MmapError::WriteHeaderFailed(error)
+    }
+}
+
+#[cfg(feature = "npz")]
+#[derive(Debug)]
+pub enum NpzError {
+    Io(std::io::Error),
+    EndOfCentralDirectoryNotFound,
+    InvalidSignature(u32, u64),
+    Malformed(String),
+    MultiDisk,
+    Encrypted(String),
+    UnsupportedMethod(String, u16),
+    MemberNotFound(String),
+    CrcMismatch(String, u32, u32),
+    ReadHeaderFailed(ReadHeaderError),
+    ReadArrayFailed(ReadArrayError),
+    #[cfg(feature = "writer")]
+    InvalidCompressionLevel(u32),
+    #[cfg(feature = "writer")]
+    DuplicateMember(String),
+    #[cfg(feature = "writer")]
+    WriteArrayFailed(WriteArrayError),
+}
+
+#[cfg(feature = "npz")]
+impl std::error::Error for NpzError {}
+
+#[cfg(feature = "npz")]
+impl std::fmt::Display for NpzError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            NpzError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            NpzError::EndOfCentralDirectoryNotFound => {
+                write!(
+                    f,
+                    "End of central directory record is not found, not a ZIP archive"
+                )
+            }
+            NpzError::InvalidSignature(signature, offset) => {
+                write!(
+                    f,
+                    "Invalid signature {:#010x} found at offset {}",
+                    signature, offset
+                )
+            }
+            NpzError::Malformed(reason) => {
+                write!(f, "Malformed archive: {}", reason)
+            }
+            NpzError::MultiDisk => {
+                write!(f, "Archives spanning multiple disks are not supported")
+            }
+            NpzError::Encrypted(name) => {
+                write!(f, "Member {} is encrypted", name)
+            }
+            NpzError::UnsupportedMethod(name, method) => {
+                write!(
+                    f,
+                    "Member {} uses an unsupported compression method {}",
+                    name, method
+                )
+            }
+            NpzError::MemberNotFound(name) => {
+                write!(f, "Member {} is not found", name)
+            }
+            NpzError::CrcMismatch(name, expected, actual) => {
+                write!(
+                    f,
+                    "CRC-32 of member {} is expected to be {:#010x}, found {:#010x}",
+                    name, expected, actual
+                )
+            }
+            NpzError::ReadHeaderFailed(error) => {
+                write!(f, "Failed to read header: {}", error)
+            }
+            NpzError::ReadArrayFailed(error) => {
+                write!(f, "Failed to read array: {}", error)
+            }
+            #[cfg(feature = "writer")]
+            NpzError::InvalidCompressionLevel(level) => {
+                write!(f, "Compression level {} is not in 0 to 9", level)
+            }
+            #[cfg(feature = "writer")]
+            NpzError::DuplicateMember(name) => {
+                write!(f, "Member {} is already in the archive", name)
+            }
+            #[cfg(feature = "writer")]
+            NpzError::WriteArrayFailed(error) => {
+                write!(f, "Failed to write array: {}", error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "npz")]
+impl From<std::io::Error> for NpzError {
+    fn from(error: std::io::Error) -> Self {
+        NpzError::Io(error)
+    }
+}
+
+#[cfg(feature = "npz")]
+impl From<ReadHeaderError> for NpzError {
+    fn from(error: ReadHeaderError) -> Self {
+        NpzError::ReadHeaderFailed(error)
+    }
+}
+
+#[cfg(feature = "npz")]
+impl From<ReadArrayError> for NpzError {
+    fn from(error: ReadArrayError) -> Self {
+        NpzError::ReadArrayFailed(error)
+    }
+}
+
+#[cfg(all(feature = "npz", feature = "writer"))]
+impl From<WriteArrayError> for NpzError {
+    fn from(error: WriteArrayError) -> Self {
+        NpzError::WriteArrayFailed(error)
+    }
+}
+
 #[cfg(feature = "writer")]
 #[derive(Debug)]
 pub enum WriteHeaderError {
     Io(std::io::Error),
     ValidationFailed(ValidationError),
-    ZeroPaddingSize,
+    InvalidVersion(u8),
+    InvalidAlignment(usize),
+    UnencodableHeader(u8),
+    HeaderTooLarge(usize, u8),
+    InsufficientSpace(usize, usize),
 }
 
 #[cfg(feature = "writer")]
@@ -76,6 +486,7 @@ impl std::error::Error for WriteHeaderError {}
 #[cfg(feature = "writer")]
 impl std::fmt::Display for WriteHeaderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        use crate::consts::HEADER_BLOCK_SIZE;
         match self {
             WriteHeaderError::Io(error) => {
                 write!(f, "{}", error)
@@ -83,8 +494,40 @@ impl std::fmt::Display for WriteHeaderError {
             WriteHeaderError::ValidationFailed(string) => {
                 write!(f, "Illegal argument: {}", string)
             }
-            WriteHeaderError::ZeroPaddingSize => {
-                write!(f, "Zero padding size is illegal: at least terminating 0x0a is necessary at the end of header")
+            WriteHeaderError::InvalidVersion(value) => {
+                write!(
+                    f,
+                    "Invalid major version: {}, which should be 1, 2 or 3",
+                    value
+                )
+            }
+            WriteHeaderError::InvalidAlignment(value) => {
+                write!(
+                    f,
+                    "Invalid alignment: {}, which should be a multiple of {}",
+                    value, HEADER_BLOCK_SIZE
+                )
+            }
+            WriteHeaderError::UnencodableHeader(version) => {
+                write!(
+                    f,
+                    "Header cannot be encoded in latin-1 required by version {}.0",
+                    version
+                )
+            }
+            WriteHeaderError::HeaderTooLarge(header_len, version) => {
+                write!(
+                    f,
+                    "Header length {} is too large for version {}.0",
+                    header_len, version
+                )
+            }
+            WriteHeaderError::InsufficientSpace(required, available) => {
+                write!(
+                    f,
+                    "Header requires {} bytes but only {} bytes are available",
+                    required, available
+                )
             }
         }
     }
@@ -103,3 +546,312 @@ impl From<ValidationError> for WriteHeaderError {
         WriteHeaderError::ValidationFailed(error)
     }
 }
+
+#[cfg(feature = "writer")]
+#[derive(Debug)]
+pub enum WriteArrayError {
+    Io(std::io::Error),
+    WriteHeaderFailed(WriteHeaderError),
+    ShapeMismatch(Vec<usize>, usize),
+}
+
+#[cfg(feature = "writer")]
+impl std::error::Error for WriteArrayError {}
+
+#[cfg(feature = "writer")]
+impl std::fmt::Display for WriteArrayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            WriteArrayError::Io(error) => {
+                write!(f, "{}", error)
+            }
+            WriteArrayError::WriteHeaderFailed(error) => {
+                write!(f, "Failed to write header: {}", error)
+            }
+            WriteArrayError::ShapeMismatch(shape, len) => {
+                write!(
+                    f,
+                    "Shape {:?} does not match the number of elements: {}",
+                    shape, len
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "writer")]
+impl From<std::io::Error> for WriteArrayError {
+    fn from(error: std::io::Error) -> Self {
+        WriteArrayError::Io(error)
+    }
+}
+
+#[cfg(feature = "writer")]
+impl From<WriteHeaderError> for WriteArrayError {
+    fn from(error: WriteHeaderError) -> Self {
+        WriteArrayError::WriteHeaderFailed(error)
+    }
+}
+
+#[cfg(feature = "writer")]
+#[derive(Debug)]
+pub enum StreamWriterError {
+    Io(std::io::Error),
+    WriteHeaderFailed(WriteHeaderError),
+    SizeOverflow(Vec<usize>),
+    IncompleteRow(usize, usize),
+}
+
+#[cfg(feature = "writer")]
+impl std::error::Error for StreamWriterError {}
+
+#[cfg(feature = "writer")]
+impl std::fmt::Display for StreamWriterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            StreamWriterError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            StreamWriterError::WriteHeaderFailed(error) => {
+                write!(f, "Failed to write header: {}", error)
+            }
+            StreamWriterError::SizeOverflow(shape) => {
+                write!(f, "Size overflows for shape: {:?}", shape)
+            }
+            StreamWriterError::IncompleteRow(count, row_len) => {
+                write!(
+                    f,
+                    "{} elements are written, which is not a multiple of the row length {}",
+                    count, row_len
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "writer")]
+impl From<std::io::Error> for StreamWriterError {
+    fn from(error: std::io::Error) -> Self {
+        StreamWriterError::Io(error)
+    }
+}
+
+#[cfg(feature = "writer")]
+impl From<WriteHeaderError> for StreamWriterError {
+    fn from(error: WriteHeaderError) -> Self {
+        StreamWriterError::WriteHeaderFailed(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+#[derive(Debug)]
+pub enum AppendError {
+    Io(std::io::Error),
+    ValidatePayloadFailed(ValidatePayloadError),
+    WriteHeaderFailed(WriteHeaderError),
+    DTypeMismatch(Descr, DType),
+    SizeOverflow(Vec<usize>),
+    NotAppendable(Vec<usize>),
+    IncompleteRow(usize, usize),
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl std::error::Error for AppendError {}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl std::fmt::Display for AppendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            AppendError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            AppendError::ValidatePayloadFailed(error) => {
+                write!(f, "Invalid file: {}", error)
+            }
+            AppendError::WriteHeaderFailed(error) => {
+                write!(f, "Failed to write header: {}", error)
+            }
+            AppendError::DTypeMismatch(descr, dtype) => {
+                write!(
+                    f,
+                    "Data type in the header {} does not match the requested type '{}'",
+                    descr, dtype
+                )
+            }
+            AppendError::SizeOverflow(shape) => {
+                write!(f, "Size overflows for shape: {:?}", shape)
+            }
+            AppendError::NotAppendable(shape) => {
+                write!(
+                    f,
+                    "Rows cannot be appended to an array of shape {:?}",
+                    shape
+                )
+            }
+            AppendError::IncompleteRow(len, row_len) => {
+                write!(
+                    f,
+                    "{} elements are given, which is not a multiple of the row length {}",
+                    len, row_len
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<std::io::Error> for AppendError {
+    fn from(error: std::io::Error) -> Self {
+        AppendError::Io(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<ValidatePayloadError> for AppendError {
+    fn from(error: ValidatePayloadError) -> Self {
+        AppendError::ValidatePayloadFailed(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<WriteHeaderError> for AppendError {
+    fn from(error: WriteHeaderError) -> Self {
+        AppendError::WriteHeaderFailed(error)
+    }
+}
+
+#[cfg(feature = "reader")]
+#[derive(Debug)]
+pub enum SelectionError {
+    Io(std::io::Error),
+    DTypeMismatch(Descr, DType),
+    InvalidHyperslab(crate::selection::Hyperslab, Vec<usize>),
+    RowOutOfBounds(usize, usize),
+    Layout(LayoutError),
+    #[cfg(feature = "writer")]
+    ShapeMismatch(Vec<usize>, usize),
+}
+
+#[cfg(feature = "reader")]
+impl std::error::Error for SelectionError {}
+
+#[cfg(feature = "reader")]
+impl std::fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            SelectionError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            SelectionError::DTypeMismatch(descr, dtype) => {
+                write!(
+                    f,
+                    "Data type in the header {} does not match the requested type '{}'",
+                    descr, dtype
+                )
+            }
+            SelectionError::InvalidHyperslab(slab, shape) => {
+                write!(
+                    f,
+                    "Hyperslab (start: {:?}, count: {:?}, step: {:?}) does not fit in shape {:?}",
+                    slab.start, slab.count, slab.step, shape
+                )
+            }
+            SelectionError::RowOutOfBounds(row, rows) => {
+                write!(f, "Row {} is out of bounds for {} rows", row, rows)
+            }
+            SelectionError::Layout(error) => {
+                write!(f, "Invalid layout: {}", error)
+            }
+            #[cfg(feature = "writer")]
+            SelectionError::ShapeMismatch(shape, len) => {
+                write!(
+                    f,
+                    "Shape {:?} does not match the number of elements: {}",
+                    shape, len
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "reader")]
+impl From<std::io::Error> for SelectionError {
+    fn from(error: std::io::Error) -> Self {
+        SelectionError::Io(error)
+    }
+}
+
+#[cfg(feature = "reader")]
+impl From<LayoutError> for SelectionError {
+    fn from(error: LayoutError) -> Self {
+        SelectionError::Layout(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+#[derive(Debug)]
+pub enum TransposeError {
+    Io(std::io::Error),
+    ReadHeaderFailed(ReadHeaderError),
+    WriteHeaderFailed(WriteHeaderError),
+    InvalidLayout(LayoutError),
+    InvalidAxes(Vec<usize>, usize),
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl std::error::Error for TransposeError {}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl std::fmt::Display for TransposeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            TransposeError::Io(error) => {
+                write!(f, "Io error: {}", error)
+            }
+            TransposeError::ReadHeaderFailed(error) => {
+                write!(f, "Failed to read header: {}", error)
+            }
+            TransposeError::WriteHeaderFailed(error) => {
+                write!(f, "Failed to write header: {}", error)
+            }
+            TransposeError::InvalidLayout(error) => {
+                write!(f, "Invalid layout: {}", error)
+            }
+            TransposeError::InvalidAxes(axes, ndim) => {
+                write!(
+                    f,
+                    "Axes {:?} are not a permutation of {} dimensions",
+                    axes, ndim
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<std::io::Error> for TransposeError {
+    fn from(error: std::io::Error) -> Self {
+        TransposeError::Io(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<ReadHeaderError> for TransposeError {
+    fn from(error: ReadHeaderError) -> Self {
+        TransposeError::ReadHeaderFailed(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<WriteHeaderError> for TransposeError {
+    fn from(error: WriteHeaderError) -> Self {
+        TransposeError::WriteHeaderFailed(error)
+    }
+}
+
+#[cfg(all(feature = "reader", feature = "writer"))]
+impl From<LayoutError> for TransposeError {
+    fn from(error: LayoutError) -> Self {
+        TransposeError::InvalidLayout(error)
+    }
+}
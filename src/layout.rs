@@ -0,0 +1,307 @@
+//! Memory layout of arrays in C (row-major) or Fortran (column-major) order.
+
+use crate::error::LayoutError;
+use crate::Header;
+
+/// Order in which the elements are stored, given by 'fortran_order' of the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// The last axis varies fastest.
+    C,
+    /// The first axis varies fastest.
+    F,
+}
+
+impl Order {
+    pub fn from_fortran_order(fortran_order: bool) -> Self {
+        if fortran_order {
+            Order::F
+        } else {
+            Order::C
+        }
+    }
+
+    pub fn is_fortran_order(&self) -> bool {
+        *self == Order::F
+    }
+}
+
+fn storage_axes(ndim: usize, order: Order) -> Vec<usize> {
+    match order {
+        Order::C => (0usize..ndim).collect(),
+        Order::F => (0usize..ndim).rev().collect(),
+    }
+}
+
+/// Shape, order and element size of an array,
+/// mapping multi-indices to positions in the payload and vice versa.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    shape: Vec<usize>,
+    order: Order,
+    itemsize: usize,
+    /// Distance between neighbouring indices of each axis in elements.
+    strides: Vec<usize>,
+    len: usize,
+}
+
+impl Layout {
+    /// Computes the strides, checking that the size and the strides in bytes do not overflow.
+    ///
+    /// The strides are checked separately, as an empty axis makes the size zero
+    /// however large the other axes are.
+    pub fn new(shape: &[usize], order: Order, itemsize: usize) -> Result<Self, LayoutError> {
+        let mut strides = vec![0usize; shape.len()];
+        let mut len: Option<usize> = Some(1usize);
+        for &n in storage_axes(shape.len(), order).iter().rev() {
+            strides[n] = len.unwrap_or(0usize);
+            len = len.and_then(|len: usize| len.checked_mul(shape[n]));
+        }
+        let len: usize = len
+            .filter(|len: &usize| len.checked_mul(itemsize).is_some())
+            .filter(|_| {
+                strides
+                    .iter()
+                    .all(|stride: &usize| stride.checked_mul(itemsize).is_some())
+            })
+            .ok_or_else(|| LayoutError::SizeOverflow(shape.to_vec()))?;
+        Ok(Layout {
+            shape: shape.to_vec(),
+            order,
+            itemsize,
+            strides,
+            len,
+        })
+    }
+
+    pub fn from_header(header: &Header) -> Result<Self, LayoutError> {
+        Layout::new(
+            &header.shape,
+            Order::from_fortran_order(header.fortran_order),
+            header.descr.itemsize(),
+        )
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    pub fn itemsize(&self) -> usize {
+        self.itemsize
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0usize
+    }
+
+    /// Strides of the axes in elements.
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    /// Strides of the axes in bytes.
+    pub fn byte_strides(&self) -> Vec<usize> {
+        self.strides
+            .iter()
+            .map(|&stride: &usize| stride * self.itemsize)
+            .collect()
+    }
+
+    /// The axis varying slowest, which is `None` for zero-dimensional arrays.
+    pub fn outermost_axis(&self) -> Option<usize> {
+        match (self.shape.len(), self.order) {
+            (0usize, _) => None,
+            (_, Order::C) => Some(0usize),
+            (ndim, Order::F) => Some(ndim - 1usize),
+        }
+    }
+
+    /// Axes ordered from the slowest to the fastest varying one.
+    pub fn storage_axes(&self) -> Vec<usize> {
+        storage_axes(self.shape.len(), self.order)
+    }
+
+    /// Position of the element in the flat buffer, or `None` if the index is out of bounds.
+    pub fn offset_of(&self, index: &[usize]) -> Option<usize> {
+        if index.len() != self.shape.len() {
+            return None;
+        }
+        index
+            .iter()
+            .zip(self.shape.iter().zip(self.strides.iter()))
+            .try_fold(0usize, |offset: usize, (&i, (&n, &stride))| {
+                (i < n).then_some(offset + i * stride)
+            })
+    }
+
+    /// Multi-index of the element at the position in the flat buffer,
+    /// or `None` if the position is out of bounds.
+    pub fn index_of(&self, offset: usize) -> Option<Vec<usize>> {
+        if self.len <= offset {
+            return None;
+        }
+        let mut index = vec![0usize; self.shape.len()];
+        let mut remainder: usize = offset;
+        for n in self.storage_axes() {
+            index[n] = remainder / self.strides[n];
+            remainder %= self.strides[n];
+        }
+        Some(index)
+    }
+
+    /// The same shape and element size in the given order.
+    ///
+    /// This fails when the strides in the given order overflow,
+    /// which may happen for empty arrays even when those in this order do not.
+    pub fn with_order(&self, order: Order) -> Result<Self, LayoutError> {
+        Layout::new(&self.shape, order, self.itemsize)
+    }
+
+    /// Rearranges a flat buffer in this layout into the given order.
+    pub fn to_order<T: Copy>(&self, data: &[T], order: Order) -> Result<Vec<T>, LayoutError> {
+        if data.len() != self.len {
+            return Err(LayoutError::ShapeMismatch(self.shape.clone(), data.len()));
+        }
+        if order == self.order || self.shape.len() <= 1usize {
+            return Ok(data.to_vec());
+        }
+        let axes: Vec<usize> = storage_axes(self.shape.len(), order);
+        Ok(Offsets::new(&self.shape, &self.strides, &axes)
+            .map(|offset: usize| data[offset])
+            .collect())
+    }
+}
+
+/// Offsets `sum(index[n] * strides[n])` of all the multi-indices within `shape`,
+/// visited with `axes[0]` varying slowest and the last of `axes` varying fastest.
+pub(crate) struct Offsets {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    axes: Vec<usize>,
+    index: Vec<usize>,
+    offset: usize,
+    done: bool,
+}
+
+impl Offsets {
+    pub(crate) fn new(shape: &[usize], strides: &[usize], axes: &[usize]) -> Self {
+        Offsets {
+            shape: shape.to_vec(),
+            strides: strides.to_vec(),
+            axes: axes.to_vec(),
+            index: vec![0usize; shape.len()],
+            offset: 0usize,
+            done: shape.contains(&0usize),
+        }
+    }
+}
+
+impl Iterator for Offsets {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.done {
+            return None;
+        }
+        let offset: usize = self.offset;
+        self.done = true;
+        for &n in self.axes.iter().rev() {
+            self.index[n] += 1usize;
+            self.offset += self.strides[n];
+            if self.index[n] < self.shape[n] {
+                self.done = false;
+                break;
+            }
+            self.offset -= self.strides[n] * self.index[n];
+            self.index[n] = 0usize;
+        }
+        Some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, Order};
+    use crate::error::LayoutError;
+
+    #[test]
+    fn strides() {
+        let c = Layout::new(&[2usize, 3usize, 4usize], Order::C, 8usize).unwrap();
+        assert_eq!(c.strides(), &[12usize, 4usize, 1usize]);
+        assert_eq!(c.byte_strides(), vec![96usize, 32usize, 8usize]);
+        assert_eq!(c.outermost_axis(), Some(0usize));
+        let f = Layout::new(&[2usize, 3usize, 4usize], Order::F, 8usize).unwrap();
+        assert_eq!(f.strides(), &[1usize, 2usize, 6usize]);
+        assert_eq!(f.outermost_axis(), Some(2usize));
+        assert_eq!(f.len(), 24usize);
+        let scalar = Layout::new(&[], Order::C, 4usize).unwrap();
+        assert_eq!(scalar.len(), 1usize);
+        assert_eq!(scalar.outermost_axis(), None);
+        assert_eq!(scalar.offset_of(&[]), Some(0usize));
+        assert!(matches!(
+            Layout::new(&[usize::MAX, 2usize], Order::C, 1usize),
+            Err(LayoutError::SizeOverflow(_))
+        ));
+        // empty, while the byte strides overflow
+        assert!(matches!(
+            Layout::new(&[5usize, 0usize, 1usize << 61], Order::C, 8usize),
+            Err(LayoutError::SizeOverflow(_))
+        ));
+        let empty = Layout::new(&[5usize, 0usize, 1usize << 61], Order::C, 1usize).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.byte_strides(), vec![0usize, 1usize << 61, 1usize]);
+    }
+
+    #[test]
+    fn offsets_and_indices() {
+        for order in [Order::C, Order::F] {
+            let layout = Layout::new(&[2usize, 3usize, 4usize], order, 1usize).unwrap();
+            for offset in 0usize..24usize {
+                let index: Vec<usize> = layout.index_of(offset).unwrap();
+                assert_eq!(layout.offset_of(&index), Some(offset));
+            }
+            assert_eq!(layout.index_of(24usize), None);
+            assert_eq!(layout.offset_of(&[1usize, 3usize, 0usize]), None);
+        }
+        let c = Layout::new(&[2usize, 3usize], Order::C, 1usize).unwrap();
+        assert_eq!(c.index_of(4usize), Some(vec![1usize, 1usize]));
+        let f = Layout::new(&[2usize, 3usize], Order::F, 1usize).unwrap();
+        assert_eq!(f.index_of(4usize), Some(vec![0usize, 2usize]));
+    }
+
+    #[test]
+    fn conversion() {
+        let c = Layout::new(&[2usize, 3usize], Order::C, 4usize).unwrap();
+        let data: Vec<i32> = vec![0, 1, 2, 10, 11, 12];
+        let converted: Vec<i32> = c.to_order(&data, Order::F).unwrap();
+        assert_eq!(converted, vec![0, 10, 1, 11, 2, 12]);
+        let f: Layout = c.with_order(Order::F).unwrap();
+        assert_eq!(f.to_order(&converted, Order::C).unwrap(), data);
+        let cube = Layout::new(&[2usize, 3usize, 4usize], Order::F, 4usize).unwrap();
+        let data: Vec<usize> = (0usize..24usize).collect();
+        let converted: Vec<usize> = cube.to_order(&data, Order::C).unwrap();
+        let target: Layout = cube.with_order(Order::C).unwrap();
+        for (offset, &value) in converted.iter().enumerate() {
+            assert_eq!(cube.index_of(value), target.index_of(offset));
+        }
+        assert!(matches!(
+            c.to_order(&[0i32; 5], Order::F),
+            Err(LayoutError::ShapeMismatch(..))
+        ));
+        // the strides after the empty axis are zero only in C order
+        let empty = Layout::new(&[1usize << 61, 0usize, 8usize], Order::C, 8usize).unwrap();
+        assert!(matches!(
+            empty.with_order(Order::F),
+            Err(LayoutError::SizeOverflow(_))
+        ));
+    }
+}
@@ -1,31 +1,599 @@
+#[cfg(all(feature = "reader", feature = "writer"))]
+pub mod append;
+pub mod cast;
+#[cfg(feature = "reader")]
+pub mod chunks;
+#[cfg(any(feature = "reader", feature = "writer"))]
 mod consts;
+pub mod dtype;
+pub mod element;
 pub mod error;
+pub mod layout;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "npz")]
+pub mod npz;
 #[cfg(feature = "reader")]
 mod reader;
+#[cfg(feature = "reader")]
+pub mod selection;
+#[cfg(feature = "writer")]
+pub mod stream;
+#[cfg(all(feature = "reader", feature = "writer"))]
+pub mod transpose;
 #[cfg(feature = "writer")]
 mod writer;
 
+pub use dtype::{DType, Descr, Field};
+pub use element::Element;
+pub use layout::{Layout, Order};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Header {
-    pub descr: String,
+    pub descr: Descr,
     pub fortran_order: bool,
     pub shape: Vec<usize>,
 }
 
+/// Options to control the layout of written headers.
+///
+/// The default values produce the same headers as `numpy.save`.
+#[cfg(feature = "writer")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriterOptions {
+    /// Major version of the format, which is decided from the header if `None`.
+    pub version: Option<u8>,
+    /// Number of digits reserved for the outermost axis of shape,
+    /// so that the header can be rewritten in-place when the array grows.
+    pub growth_axis_max_digits: usize,
+    /// Alignment of the payload in bytes, which should be a multiple of 64.
+    pub alignment: usize,
+}
+
+#[cfg(feature = "writer")]
+impl Default for WriterOptions {
+    fn default() -> Self {
+        WriterOptions {
+            version: None,
+            growth_axis_max_digits: consts::GROWTH_AXIS_MAX_DIGITS,
+            alignment: consts::HEADER_BLOCK_SIZE,
+        }
+    }
+}
+
+/// Header together with the layout of the file it is read from.
 #[cfg(feature = "reader")]
-pub fn read_header(f: &mut std::fs::File) -> Result<Header, error::ReadHeaderError> {
-    reader::check_magic_string(f)?;
-    let major_version: u8 = reader::fetch_major_version(f)?;
-    reader::check_minor_version(f)?;
-    let header_len: usize = reader::fetch_header_len(f, major_version)?;
-    let header: Header = reader::fetch_header(f, header_len)?;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderInfo {
+    pub header: Header,
+    /// Major version of the format (the minor version is always 0).
+    pub major_version: u8,
+    /// Value of HEADER_LEN, i.e. the size of the dictionary and the padding in bytes.
+    pub header_len: usize,
+    /// Size of the whole header in bytes, which is where the payload starts.
+    pub data_offset: usize,
+    /// Size of one element in bytes.
+    pub itemsize: usize,
+    /// Number of elements, i.e. the product of the shape.
+    pub count: usize,
+    /// Expected size of the payload in bytes.
+    pub payload_size: usize,
+}
+
+/// Reads the NPY header from any byte stream (a file, a `Cursor`, stdin, ...).
+///
+/// The stream is left positioned at the beginning of the payload.
+#[cfg(feature = "reader")]
+pub fn read_header<R: std::io::Read>(f: &mut R) -> Result<Header, error::ReadHeaderError> {
+    let (header, _, _): (Header, u8, usize) = reader::read_header_parts(f)?;
     Ok(header)
 }
 
+/// Reads the NPY header as [`read_header`] does, also reporting the version and the sizes.
+///
+/// Unlike [`read_header`], this fails when the size of the payload overflows `usize`.
+#[cfg(feature = "reader")]
+pub fn read_header_info<R: std::io::Read>(f: &mut R) -> Result<HeaderInfo, error::ReadHeaderError> {
+    let (header, major_version, header_len): (Header, u8, usize) = reader::read_header_parts(f)?;
+    reader::prepare_header_info(header, major_version, header_len)
+}
+
+/// Checks that `actual` bytes following the header are exactly the payload the header describes.
+#[cfg(feature = "reader")]
+pub fn check_payload_size(
+    header_info: &HeaderInfo,
+    actual: u64,
+) -> Result<(), error::ValidatePayloadError> {
+    let expected: u64 = header_info.payload_size as u64;
+    if actual < expected {
+        return Err(error::ValidatePayloadError::Truncated(expected, actual));
+    }
+    if expected < actual {
+        return Err(error::ValidatePayloadError::TrailingBytes(expected, actual));
+    }
+    Ok(())
+}
+
+/// Reads the header and checks the size of the payload against the end of the stream.
+///
+/// The stream is expected to be positioned at the beginning of the NPY data,
+/// and is left positioned at the beginning of the payload.
+#[cfg(feature = "reader")]
+pub fn validate_payload<R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+) -> Result<HeaderInfo, error::ValidatePayloadError> {
+    use std::io::SeekFrom;
+    let header_info: HeaderInfo = read_header_info(f)?;
+    let data_start: u64 = f.stream_position()?;
+    let end: u64 = f.seek(SeekFrom::End(0i64))?;
+    f.seek(SeekFrom::Start(data_start))?;
+    check_payload_size(&header_info, end.saturating_sub(data_start))?;
+    Ok(header_info)
+}
+
+/// Reads the payload following the header as a flat vector of `T`,
+/// swapping bytes if the file is not in the native byte order.
+///
+/// The elements are returned in the order stored in the file,
+/// which is given by `header.fortran_order`.
+#[cfg(feature = "reader")]
+pub fn read_array<T: Element>(
+    f: &mut impl std::io::Read,
+    header: &Header,
+) -> Result<Vec<T>, error::ReadArrayError> {
+    let byte_order = element::byte_order_of::<T>(&header.descr)
+        .ok_or_else(|| error::ReadArrayError::DTypeMismatch(header.descr.clone(), T::dtype()))?;
+    let size: usize = header
+        .shape
+        .iter()
+        .try_fold(T::dtype().itemsize, |size: usize, &n: &usize| {
+            size.checked_mul(n)
+        })
+        .ok_or_else(|| error::ReadArrayError::SizeOverflow(header.shape.clone()))?;
+    let buf: Vec<u8> = reader::read_payload(f, size)?;
+    Ok(element::decode::<T>(&buf, byte_order))
+}
+
+/// Reads the payload as [`read_array`] does, converting the elements to `T`
+/// if the data type in the header can be cast to it under the given rule.
+#[cfg(feature = "reader")]
+pub fn read_array_cast<T: cast::Castable>(
+    f: &mut impl std::io::Read,
+    header: &Header,
+    casting: cast::Casting,
+) -> Result<Vec<T>, error::ReadArrayError> {
+    let target: DType = T::dtype();
+    let (source, decoder): (&DType, cast::Decoder) = header
+        .descr
+        .as_simple()
+        .filter(|source: &&DType| cast::can_cast(source, &target, casting))
+        .and_then(|source: &DType| Some((source, cast::decoder_of(source)?)))
+        .ok_or_else(|| error::ReadArrayError::InvalidCast(header.descr.clone(), target, casting))?;
+    let size: usize = header
+        .shape
+        .iter()
+        .try_fold(source.itemsize, |size: usize, &n: &usize| {
+            size.checked_mul(n)
+        })
+        .ok_or_else(|| error::ReadArrayError::SizeOverflow(header.shape.clone()))?;
+    let buf: Vec<u8> = reader::read_payload(f, size)?;
+    Ok(buf
+        .chunks_exact(source.itemsize)
+        .map(|chunk: &[u8]| T::from_scalar(decoder(chunk, source.byte_order)))
+        .collect())
+}
+
+/// Writes the NPY header to any byte sink, after which the payload is expected.
 #[cfg(feature = "writer")]
-pub fn write_header(f: &mut std::fs::File, header: &Header) -> Result<(), error::WriteHeaderError> {
-    let dict: Vec<u8> = writer::prepare_dictionary(header)?;
-    let buffer_info: writer::BufferInfo = writer::prepare_buffer_info(dict.len());
-    let padding: Vec<u8> = writer::prepare_padding(&buffer_info)?;
-    writer::write_all(f, &buffer_info, &dict, &padding)?;
+pub fn write_header<W: std::io::Write>(
+    f: &mut W,
+    header: &Header,
+    options: &WriterOptions,
+) -> Result<(), error::WriteHeaderError> {
+    let buf: Vec<u8> = writer::prepare_header(header, options)?;
+    f.write_all(&buf)?;
     Ok(())
 }
+
+/// Writes a whole array, deriving 'descr' from `T`.
+///
+/// `data` is stored as is in the native byte order,
+/// and hence should be arranged following `fortran_order`.
+/// The header and the payload are passed to the sink in a single call.
+#[cfg(feature = "writer")]
+pub fn write_array<T: Element>(
+    f: &mut impl std::io::Write,
+    shape: &[usize],
+    fortran_order: bool,
+    data: &[T],
+) -> Result<(), error::WriteArrayError> {
+    let count: Option<usize> = shape
+        .iter()
+        .try_fold(1usize, |count: usize, &n: &usize| count.checked_mul(n));
+    if count != Some(data.len()) {
+        return Err(error::WriteArrayError::ShapeMismatch(
+            shape.to_vec(),
+            data.len(),
+        ));
+    }
+    let dtype: DType = T::dtype();
+    let byte_order: dtype::ByteOrder = dtype.byte_order;
+    let header = Header {
+        descr: Descr::Simple(dtype),
+        fortran_order,
+        shape: shape.to_vec(),
+    };
+    let mut buf: Vec<u8> = writer::prepare_header(&header, &WriterOptions::default())?;
+    element::encode(data, byte_order, &mut buf);
+    f.write_all(&buf)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "reader", feature = "writer"))]
+mod tests {
+    use super::{
+        read_array, read_array_cast, read_header, read_header_info, validate_payload, write_array,
+        write_header, DType, Descr, Element, Header, HeaderInfo, WriterOptions,
+    };
+    use crate::error::{ReadArrayError, WriteArrayError};
+    use crate::error::{ReadHeaderError, ValidatePayloadError, WriteHeaderError};
+
+    #[test]
+    fn round_trip_in_memory() {
+        let header = Header {
+            descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+            fortran_order: true,
+            shape: vec![3usize, 5usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        buf.extend_from_slice(&[0xffu8; 4]);
+        let mut cursor = std::io::Cursor::new(buf);
+        let loaded: Header = read_header(&mut cursor).unwrap();
+        assert_eq!(loaded.descr, header.descr);
+        assert_eq!(loaded.fortran_order, header.fortran_order);
+        assert_eq!(loaded.shape, header.shape);
+        // the stream is left at the beginning of the payload
+        let mut rest = Vec::<u8>::new();
+        std::io::Read::read_to_end(&mut cursor, &mut rest).unwrap();
+        assert_eq!(rest, [0xffu8; 4]);
+    }
+
+    #[test]
+    fn read_array_with_byte_swap() {
+        let header = Header {
+            descr: Descr::Simple(">i2".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![2usize, 2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        buf.extend_from_slice(&[0x00, 0x01, 0x01, 0x00, 0xff, 0xff, 0x80, 0x00]);
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        let position = cursor.position();
+        let data: Vec<i16> = read_array::<i16>(&mut cursor, &header).unwrap();
+        assert_eq!(data, [1i16, 256i16, -1i16, i16::MIN]);
+        // mismatching types are rejected
+        cursor.set_position(position);
+        assert!(matches!(
+            read_array::<u16>(&mut cursor, &header),
+            Err(ReadArrayError::DTypeMismatch(_, _))
+        ));
+        // payload is too short
+        cursor.set_position(position + 2u64);
+        assert!(matches!(
+            read_array::<i16>(&mut cursor, &header),
+            Err(ReadArrayError::Truncated(8usize, 6usize))
+        ));
+    }
+
+    #[test]
+    fn read_array_with_cast() {
+        use crate::cast::Casting;
+        let header = Header {
+            descr: Descr::Simple(">f8".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![3usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        for value in [1.5f64, -2f64, 3e9f64] {
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        let position = cursor.position();
+        let data: Vec<f64> = read_array_cast(&mut cursor, &header, Casting::Safe).unwrap();
+        assert_eq!(data, [1.5f64, -2f64, 3e9f64]);
+        cursor.set_position(position);
+        assert!(matches!(
+            read_array_cast::<f32>(&mut cursor, &header, Casting::Safe),
+            Err(ReadArrayError::InvalidCast(_, _, Casting::Safe))
+        ));
+        let data: Vec<f32> = read_array_cast(&mut cursor, &header, Casting::SameKind).unwrap();
+        assert_eq!(data, [1.5f32, -2f32, 3e9f32]);
+        cursor.set_position(position);
+        assert!(matches!(
+            read_array_cast::<i32>(&mut cursor, &header, Casting::SameKind),
+            Err(ReadArrayError::InvalidCast(..))
+        ));
+        // saturated as Rust does, where NumPy leaves it undefined
+        let data: Vec<i32> = read_array_cast(&mut cursor, &header, Casting::Unsafe).unwrap();
+        assert_eq!(data, [1i32, -2i32, i32::MAX]);
+        // integers and half precision floats are widened
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[2usize], false, &[-3i16, 7i16]).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        let data: Vec<f64> = read_array_cast(&mut cursor, &header, Casting::Safe).unwrap();
+        assert_eq!(data, [-3f64, 7f64]);
+        let header = Header {
+            descr: Descr::Simple("<f2".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![3usize],
+        };
+        let payload: [u16; 3] = [0x3c00u16, 0xc100u16, 0x0001u16];
+        let buf: Vec<u8> = payload.iter().flat_map(|x: &u16| x.to_le_bytes()).collect();
+        let data: Vec<f32> = read_array_cast(&mut buf.as_slice(), &header, Casting::Safe).unwrap();
+        assert_eq!(data, [1f32, -2.5f32, 2f32.powi(-24i32)]);
+        // real values are promoted to complex ones with zero imaginary parts
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[2usize], false, &[1.5f32, -2f32]).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        let data: Vec<[f64; 2]> = read_array_cast(&mut cursor, &header, Casting::Safe).unwrap();
+        assert_eq!(data, [[1.5f64, 0f64], [-2f64, 0f64]]);
+    }
+
+    #[test]
+    fn complex_round_trip() {
+        let data: Vec<[f64; 2]> = vec![[1f64, -1f64], [0.5f64, 2f64]];
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[2usize], false, &data).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        assert_eq!(header.descr.to_string(), "'<c16'");
+        let position = cursor.position();
+        assert_eq!(read_array::<[f64; 2]>(&mut cursor, &header).unwrap(), data);
+        cursor.set_position(position);
+        assert!(matches!(
+            read_array::<[f32; 2]>(&mut cursor, &header),
+            Err(ReadArrayError::DTypeMismatch(..))
+        ));
+        #[cfg(feature = "num-complex")]
+        {
+            use num_complex::Complex;
+            cursor.set_position(position);
+            let data: Vec<Complex<f64>> = read_array(&mut cursor, &header).unwrap();
+            assert_eq!(
+                data,
+                [Complex::new(1f64, -1f64), Complex::new(0.5f64, 2f64)]
+            );
+        }
+    }
+
+    #[test]
+    fn write_array_round_trip() {
+        let data: Vec<f32> = (0..6).map(|n: i32| n as f32).collect();
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[2usize, 3usize], true, &data).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        assert_eq!(header.descr.as_simple(), Some(&f32::dtype()));
+        assert!(header.fortran_order);
+        assert_eq!(header.shape, vec![2usize, 3usize]);
+        assert_eq!(read_array::<f32>(&mut cursor, &header).unwrap(), data);
+        // the number of elements should be consistent with the shape
+        let mut buf = Vec::<u8>::new();
+        assert!(matches!(
+            write_array(&mut buf, &[4usize, 2usize], false, &data),
+            Err(WriteArrayError::ShapeMismatch(_, 6usize))
+        ));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn numpy_compatible_header() {
+        // np.save(f, np.zeros((3, 5)))
+        let header = Header {
+            descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![3usize, 5usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        let mut expected: Vec<u8> = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+        expected.extend_from_slice(b"{'descr': '<f8', 'fortran_order': False, 'shape': (3, 5), }");
+        expected.extend_from_slice(&[b' '; 58]);
+        expected.push(b'\n');
+        assert_eq!(buf, expected);
+        // np.save(f, np.asfortranarray(np.zeros((1, 12345), dtype=">i4")))
+        let header = Header {
+            descr: Descr::Simple(">i4".parse::<DType>().unwrap()),
+            fortran_order: true,
+            shape: vec![1usize, 12345usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        let mut expected: Vec<u8> = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+        expected
+            .extend_from_slice(b"{'descr': '>i4', 'fortran_order': True, 'shape': (1, 12345), }");
+        expected.extend_from_slice(&[b' '; 55]);
+        expected.push(b'\n');
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn unicode_field_names() {
+        let header = Header {
+            descr: Descr::Record(vec![crate::Field {
+                name: "速度".to_string(),
+                descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+                shape: vec![],
+            }]),
+            fortran_order: false,
+            shape: vec![2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        // major version
+        assert_eq!(buf[6], 3u8);
+        assert_eq!(buf.len() % 64usize, 0usize);
+        let loaded: Header = read_header(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.descr, header.descr);
+        // latin-1 names are kept in version 1.0
+        let header = Header {
+            descr: Descr::Record(vec![crate::Field {
+                name: "é".to_string(),
+                descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+                shape: vec![],
+            }]),
+            fortran_order: false,
+            shape: vec![2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        assert_eq!(buf[6], 1u8);
+        assert!(buf.contains(&0xe9u8));
+        let loaded: Header = read_header(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.descr, header.descr);
+    }
+
+    #[test]
+    fn writer_options() {
+        let header = Header {
+            descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![3usize, 5usize],
+        };
+        let options = WriterOptions {
+            version: Some(2u8),
+            growth_axis_max_digits: 0usize,
+            alignment: 4096usize,
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &options).unwrap();
+        assert_eq!(buf.len(), 4096usize);
+        assert_eq!(buf[6], 2u8);
+        let loaded: Header = read_header(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.shape, header.shape);
+        // invalid options
+        let options = WriterOptions {
+            version: Some(4u8),
+            ..WriterOptions::default()
+        };
+        assert!(matches!(
+            write_header(&mut Vec::<u8>::new(), &header, &options),
+            Err(WriteHeaderError::InvalidVersion(4u8))
+        ));
+        let options = WriterOptions {
+            alignment: 100usize,
+            ..WriterOptions::default()
+        };
+        assert!(matches!(
+            write_header(&mut Vec::<u8>::new(), &header, &options),
+            Err(WriteHeaderError::InvalidAlignment(100usize))
+        ));
+        // forced version cannot encode non-latin-1 headers
+        let header = Header {
+            descr: Descr::Record(vec![crate::Field {
+                name: "速度".to_string(),
+                descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+                shape: vec![],
+            }]),
+            fortran_order: false,
+            shape: vec![],
+        };
+        let options = WriterOptions {
+            version: Some(1u8),
+            ..WriterOptions::default()
+        };
+        assert!(matches!(
+            write_header(&mut Vec::<u8>::new(), &header, &options),
+            Err(WriteHeaderError::UnencodableHeader(1u8))
+        ));
+    }
+
+    #[test]
+    fn empty_arrays() {
+        let data: Vec<u16> = Vec::new();
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[0usize, 5usize], false, &data).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        let header: Header = read_header(&mut cursor).unwrap();
+        assert_eq!(header.shape, vec![0usize, 5usize]);
+        assert_eq!(read_array::<u16>(&mut cursor, &header).unwrap(), data);
+        assert!(matches!(
+            write_array(&mut Vec::<u8>::new(), &[0usize, 5usize], false, &[0u16]),
+            Err(WriteArrayError::ShapeMismatch(_, 1usize))
+        ));
+    }
+
+    #[test]
+    fn header_info() {
+        let data: Vec<i32> = (0..12).collect();
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[3usize, 4usize], false, &data).unwrap();
+        let info: HeaderInfo = read_header_info(&mut buf.as_slice()).unwrap();
+        assert_eq!(info.major_version, 1u8);
+        assert_eq!(info.data_offset, 128usize);
+        assert_eq!(info.header_len, 118usize);
+        assert_eq!(info.itemsize, 4usize);
+        assert_eq!(info.count, 12usize);
+        assert_eq!(info.payload_size, 48usize);
+        assert_eq!(info.data_offset + info.payload_size, buf.len());
+        // sizes overflowing usize are rejected
+        let header = Header {
+            descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![usize::MAX / 4usize, 2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        assert!(matches!(
+            read_header_info(&mut buf.as_slice()),
+            Err(ReadHeaderError::SizeOverflow(_))
+        ));
+        // while the header itself is valid
+        assert_eq!(read_header(&mut buf.as_slice()).unwrap(), header);
+    }
+
+    #[test]
+    fn payload_validation() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut buf = Vec::<u8>::new();
+        write_array(&mut buf, &[10usize], false, &data).unwrap();
+        let mut cursor = std::io::Cursor::new(buf.clone());
+        let info: HeaderInfo = validate_payload(&mut cursor).unwrap();
+        assert_eq!(cursor.position(), info.data_offset as u64);
+        assert_eq!(read_array::<u8>(&mut cursor, &info.header).unwrap(), data);
+        // truncated
+        let mut cursor = std::io::Cursor::new(buf[..buf.len() - 3usize].to_vec());
+        assert!(matches!(
+            validate_payload(&mut cursor),
+            Err(ValidatePayloadError::Truncated(10u64, 7u64))
+        ));
+        // trailing garbage
+        let mut garbage = buf.clone();
+        garbage.push(0u8);
+        let mut cursor = std::io::Cursor::new(garbage);
+        assert!(matches!(
+            validate_payload(&mut cursor),
+            Err(ValidatePayloadError::TrailingBytes(10u64, 11u64))
+        ));
+        // overflow
+        let header = Header {
+            descr: Descr::Simple("<f8".parse::<DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![usize::MAX, 2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        write_header(&mut buf, &header, &WriterOptions::default()).unwrap();
+        let mut cursor = std::io::Cursor::new(buf);
+        assert!(matches!(
+            validate_payload(&mut cursor),
+            Err(ValidatePayloadError::SizeOverflow(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Zero-copy views of NPY files through memory mapping,
+//! similar to `numpy.lib.format.open_memmap`.
+
+use crate::element::{self, Plain};
+use crate::error::MmapError;
+use crate::HeaderInfo;
+
+/// A read-only NPY array whose payload is viewed in-place as `&[T]`.
+pub struct MmapArray<T: Plain> {
+    mmap: memmap2::Mmap,
+    header_info: HeaderInfo,
+    _marker: std::marker::PhantomData<T>,
+}
+
+/// Checks that the payload of the mapped file can be viewed as a slice of `T`.
+fn check_view<T: Plain>(header_info: &HeaderInfo, base: *const u8) -> Result<(), MmapError> {
+    let descr = &header_info.header.descr;
+    if element::byte_order_of::<T>(descr).is_none() {
+        return Err(MmapError::DTypeMismatch(descr.clone(), T::dtype()));
+    }
+    if let Some(dtype) = descr.as_simple() {
+        if !dtype.is_native_endian() {
+            return Err(MmapError::NonNativeByteOrder(dtype.clone()));
+        }
+    }
+    let alignment: usize = std::mem::align_of::<T>();
+    if !(base as usize + header_info.data_offset).is_multiple_of(alignment) {
+        return Err(MmapError::Misaligned(header_info.data_offset, alignment));
+    }
+    Ok(())
+}
+
+impl<T: Plain> MmapArray<T> {
+    /// Maps the whole file and validates its header against `T`.
+    ///
+    /// Only files in the native byte order whose payload is aligned for `T` can be viewed,
+    /// which is the case for files written with the default 64-byte alignment.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other process,
+    /// while it is mapped.
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, MmapError> {
+        let mut file = std::fs::File::open(path)?;
+        let header_info: HeaderInfo = crate::validate_payload(&mut file)?;
+        let mmap = memmap2::Mmap::map(&file)?;
+        check_view::<T>(&header_info, mmap.as_ptr())?;
+        Ok(MmapArray {
+            mmap,
+            header_info,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    pub fn header_info(&self) -> &HeaderInfo {
+        &self.header_info
+    }
+
+    /// The elements in the order stored in the file, see `Header::fortran_order`.
+    pub fn as_slice(&self) -> &[T] {
+        let data: *const u8 = self.mmap[self.header_info.data_offset..].as_ptr();
+        // SAFETY: the size, the type and the alignment are checked on opening
+        unsafe { std::slice::from_raw_parts(data as *const T, self.header_info.count) }
+    }
+}
+
+impl<T: Plain> std::ops::Deref for MmapArray<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+/// A writable NPY array whose payload is viewed in-place as `&mut [T]`.
+///
+/// Modifications are written back to the file by the operating system,
+/// or explicitly by [`MmapArrayMut::flush`].
+pub struct MmapArrayMut<T: Plain> {
+    mmap: memmap2::MmapMut,
+    header_info: HeaderInfo,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Plain> MmapArrayMut<T> {
+    /// Creates (or truncates) a file with the given shape and maps it, as `mode='w+'` does.
+    ///
+    /// The file is extended to the full payload size with `set_len`,
+    /// which gives a sparse file filled with zeros on most file systems.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by any other means while it is mapped.
+    #[cfg(feature = "writer")]
+    pub unsafe fn create<P: AsRef<std::path::Path>>(
+        path: P,
+        shape: &[usize],
+        fortran_order: bool,
+    ) -> Result<Self, MmapError> {
+        use std::io::Write;
+        let header = crate::Header {
+            descr: T::dtype().into(),
+            fortran_order,
+            shape: shape.to_vec(),
+        };
+        let mut buf = Vec::<u8>::new();
+        crate::write_header(&mut buf, &header, &crate::WriterOptions::default())?;
+        let header_info: HeaderInfo = crate::read_header_info(&mut buf.as_slice())
+            .map_err(|error| MmapError::ValidatePayloadFailed(error.into()))?;
+        let file_size: u64 = (header_info.data_offset as u64)
+            .checked_add(header_info.payload_size as u64)
+            .ok_or_else(|| {
+                MmapError::ValidatePayloadFailed(crate::error::ValidatePayloadError::SizeOverflow(
+                    header_info.header.shape.clone(),
+                ))
+            })?;
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(&buf)?;
+        file.set_len(file_size)?;
+        let mmap = memmap2::MmapMut::map_mut(&file)?;
+        check_view::<T>(&header_info, mmap.as_ptr())?;
+        Ok(MmapArrayMut {
+            mmap,
+            header_info,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Maps an existing file for reading and writing, as `mode='r+'` does.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by any other means while it is mapped.
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, MmapError> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+        let header_info: HeaderInfo = crate::validate_payload(&mut file)?;
+        let mmap = memmap2::MmapMut::map_mut(&file)?;
+        check_view::<T>(&header_info, mmap.as_ptr())?;
+        Ok(MmapArrayMut {
+            mmap,
+            header_info,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    pub fn header_info(&self) -> &HeaderInfo {
+        &self.header_info
+    }
+
+    /// The elements in the order stored in the file, see `Header::fortran_order`.
+    pub fn as_slice(&self) -> &[T] {
+        let data: *const u8 = self.mmap[self.header_info.data_offset..].as_ptr();
+        // SAFETY: the size, the type and the alignment are checked on opening
+        unsafe { std::slice::from_raw_parts(data as *const T, self.header_info.count) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let data: *mut u8 = self.mmap[self.header_info.data_offset..].as_mut_ptr();
+        // SAFETY: the size, the type and the alignment are checked on opening
+        unsafe { std::slice::from_raw_parts_mut(data as *mut T, self.header_info.count) }
+    }
+
+    /// Synchronously writes the modifications back to the file.
+    pub fn flush(&self) -> Result<(), MmapError> {
+        self.mmap.flush()?;
+        Ok(())
+    }
+}
+
+impl<T: Plain> std::ops::Deref for MmapArrayMut<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Plain> std::ops::DerefMut for MmapArrayMut<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+#[cfg(all(test, feature = "writer"))]
+mod tests {
+    use super::{MmapArray, MmapArrayMut};
+    use crate::error::{MmapError, ValidatePayloadError};
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rust_npy_io_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn view() {
+        let path = temp_file("mmap_view.npy");
+        let data: Vec<f64> = (0..15).map(|n: i32| n as f64).collect();
+        let mut file = std::fs::File::create(&path).unwrap();
+        crate::write_array(&mut file, &[3usize, 5usize], false, &data).unwrap();
+        drop(file);
+        let array = unsafe { MmapArray::<f64>::open(&path) }.unwrap();
+        assert_eq!(array.header_info().header.shape, vec![3usize, 5usize]);
+        assert_eq!(&array[..], data.as_slice());
+        assert!(matches!(
+            unsafe { MmapArray::<i64>::open(&path) },
+            Err(MmapError::DTypeMismatch(_, _))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn non_native_byte_order() {
+        let path = temp_file("mmap_non_native.npy");
+        let descr: &str = if cfg!(target_endian = "little") {
+            ">i4"
+        } else {
+            "<i4"
+        };
+        let header = crate::Header {
+            descr: descr.parse::<crate::DType>().unwrap().into(),
+            fortran_order: false,
+            shape: vec![2usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        crate::write_header(&mut buf, &header, &crate::WriterOptions::default()).unwrap();
+        buf.extend_from_slice(&[0u8; 8]);
+        std::fs::write(&path, &buf).unwrap();
+        assert!(matches!(
+            unsafe { MmapArray::<i32>::open(&path) },
+            Err(MmapError::NonNativeByteOrder(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn create_and_mutate() {
+        let path = temp_file("mmap_create.npy");
+        {
+            let mut array =
+                unsafe { MmapArrayMut::<i32>::create(&path, &[2usize, 3usize], true) }.unwrap();
+            assert_eq!(array.as_slice(), &[0i32; 6]);
+            array.copy_from_slice(&[1i32, 2i32, 3i32, 4i32, 5i32, 6i32]);
+            array.flush().unwrap();
+        }
+        {
+            let mut array = unsafe { MmapArrayMut::<i32>::open(&path) }.unwrap();
+            assert!(array.header_info().header.fortran_order);
+            array[5] = 60i32;
+        }
+        let mut file = std::fs::File::open(&path).unwrap();
+        let info = crate::validate_payload(&mut file).unwrap();
+        assert_eq!(info.header.shape, vec![2usize, 3usize]);
+        assert_eq!(
+            crate::read_array::<i32>(&mut file, &info.header).unwrap(),
+            [1i32, 2i32, 3i32, 4i32, 5i32, 60i32]
+        );
+        std::fs::remove_file(&path).unwrap();
+        // the payload fits in usize, but not together with the header
+        assert!(matches!(
+            unsafe { MmapArrayMut::<u8>::create(&path, &[usize::MAX - 1usize], false) },
+            Err(MmapError::ValidatePayloadFailed(
+                ValidatePayloadError::SizeOverflow(_)
+            ))
+        ));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,556 @@
+//! NPZ archives, i.e. ZIP files of NPY members as `numpy.savez` and `numpy.savez_compressed` produce.
+
+mod zip;
+
+#[cfg(feature = "writer")]
+use std::io::Write;
+
+use crate::error::NpzError;
+use crate::{Element, Header};
+
+/// Random access to the members of an NPZ archive.
+pub struct NpzReader<R: std::io::Read + std::io::Seek> {
+    inner: R,
+    entries: Vec<zip::Entry>,
+}
+
+impl<R: std::io::Read + std::io::Seek> NpzReader<R> {
+    /// Reads the central directory of the archive.
+    pub fn new(mut inner: R) -> Result<Self, NpzError> {
+        let entries: Vec<zip::Entry> = zip::read_entries(&mut inner)?;
+        Ok(NpzReader { inner, entries })
+    }
+
+    /// Names of the arrays, i.e. the member names without the `.npy` suffix,
+    /// which agree with the keys of `numpy.lib.npyio.NpzFile`.
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry: &zip::Entry| {
+                entry
+                    .name
+                    .strip_suffix(".npy")
+                    .unwrap_or(entry.name.as_str())
+            })
+            .collect()
+    }
+
+    /// Opens a member as a stream of the NPY data,
+    /// which is given either by the array name or by the member name.
+    ///
+    /// The CRC-32 of the member is verified when the last byte is read.
+    pub fn by_name(&mut self, name: &str) -> Result<NpzMember<'_, R>, NpzError> {
+        let member_name: String = format!("{}.npy", name);
+        let entry: &zip::Entry = self
+            .entries
+            .iter()
+            .find(|entry: &&zip::Entry| entry.name == name)
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .find(|entry: &&zip::Entry| entry.name == member_name)
+            })
+            .ok_or_else(|| NpzError::MemberNotFound(name.to_string()))?;
+        if entry.flags & zip::FLAG_ENCRYPTED != 0u16 {
+            return Err(NpzError::Encrypted(entry.name.clone()));
+        }
+        let offset: u64 = zip::data_offset(&mut self.inner, entry)?;
+        self.inner.seek(std::io::SeekFrom::Start(offset))?;
+        let data = std::io::Read::take(&mut self.inner, entry.compressed_size);
+        let decoder = match entry.method {
+            zip::METHOD_STORED => Decoder::Stored(data),
+            zip::METHOD_DEFLATED => Decoder::Deflated(flate2::read::DeflateDecoder::new(data)),
+            method => return Err(NpzError::UnsupportedMethod(entry.name.clone(), method)),
+        };
+        Ok(NpzMember {
+            name: entry.name.clone(),
+            decoder,
+            crc: flate2::Crc::new(),
+            crc32: entry.crc32,
+            remaining: entry.uncompressed_size,
+        })
+    }
+
+    /// Reads a whole member as [`crate::read_header`] and [`crate::read_array`] do,
+    /// verifying its CRC-32.
+    pub fn read_array<T: Element>(&mut self, name: &str) -> Result<(Header, Vec<T>), NpzError> {
+        let mut member: NpzMember<'_, R> = self.by_name(name)?;
+        let header: Header = crate::read_header(&mut member)?;
+        let data: Vec<T> = crate::read_array::<T>(&mut member, &header)?;
+        std::io::copy(&mut member, &mut std::io::sink())?;
+        Ok((header, data))
+    }
+}
+
+enum Decoder<'a, R: std::io::Read> {
+    Stored(std::io::Take<&'a mut R>),
+    Deflated(flate2::read::DeflateDecoder<std::io::Take<&'a mut R>>),
+}
+
+/// A member of an NPZ archive, which is read as a standalone NPY stream.
+pub struct NpzMember<'a, R: std::io::Read + std::io::Seek> {
+    name: String,
+    decoder: Decoder<'a, R>,
+    crc: flate2::Crc,
+    crc32: u32,
+    remaining: u64,
+}
+
+impl<R: std::io::Read + std::io::Seek> NpzMember<'_, R> {
+    /// Name of the member in the archive, including the `.npy` suffix.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<R: std::io::Read + std::io::Seek> std::io::Read for NpzMember<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size: usize = buf
+            .len()
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        if size == 0usize {
+            return Ok(0usize);
+        }
+        let buf: &mut [u8] = &mut buf[..size];
+        let size: usize = match &mut self.decoder {
+            Decoder::Stored(data) => data.read(buf)?,
+            Decoder::Deflated(decoder) => decoder.read(buf)?,
+        };
+        if size == 0usize {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        }
+        self.crc.update(&buf[..size]);
+        self.remaining -= size as u64;
+        if self.remaining == 0u64 && self.crc.sum() != self.crc32 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                NpzError::CrcMismatch(self.name.clone(), self.crc32, self.crc.sum()),
+            ));
+        }
+        Ok(size)
+    }
+}
+
+/// Compression applied to the members of written archives.
+#[cfg(feature = "writer")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression, as `numpy.savez` does.
+    Stored,
+    /// Deflate with a level from 0 (fastest) to 9 (smallest), as `numpy.savez_compressed` does with 6.
+    Deflated(u32),
+}
+
+/// Sequential writer of NPZ archives.
+///
+/// Each member is encoded in memory before being passed to the sink,
+/// and the archive is completed by [`NpzWriter::finish`], which writes the central directory.
+///
+/// As with [`crate::stream::StreamWriter`], dropping the writer without [`NpzWriter::finish`]
+/// completes the archive as well, but errors, e.g. I/O ones, are ignored
+/// and may leave an archive without the central directory.
+/// Call [`NpzWriter::finish`] to handle them.
+#[cfg(feature = "writer")]
+pub struct NpzWriter<W: std::io::Write> {
+    inner: Option<W>,
+    compression: Compression,
+    offset: u64,
+    entries: Vec<zip::Entry>,
+    positional: usize,
+}
+
+#[cfg(feature = "writer")]
+impl<W: std::io::Write> NpzWriter<W> {
+    pub fn new(inner: W, compression: Compression) -> Result<Self, NpzError> {
+        if let Compression::Deflated(level) = compression {
+            if 9u32 < level {
+                return Err(NpzError::InvalidCompressionLevel(level));
+            }
+        }
+        Ok(NpzWriter {
+            inner: Some(inner),
+            compression,
+            offset: 0u64,
+            entries: Vec::new(),
+            positional: 0usize,
+        })
+    }
+
+    /// Adds an array as [`crate::write_array`] does, stored as the member `<name>.npy`.
+    pub fn add_array<T: Element>(
+        &mut self,
+        name: &str,
+        shape: &[usize],
+        fortran_order: bool,
+        data: &[T],
+    ) -> Result<(), NpzError> {
+        let mut buf = Vec::<u8>::new();
+        crate::write_array(&mut buf, shape, fortran_order, data)?;
+        self.add_member(format!("{}.npy", name), &buf)
+    }
+
+    /// Adds an array named after its position among unnamed arrays,
+    /// i.e. `arr_0`, `arr_1`, ..., following the positional arguments of `numpy.savez`.
+    pub fn push_array<T: Element>(
+        &mut self,
+        shape: &[usize],
+        fortran_order: bool,
+        data: &[T],
+    ) -> Result<(), NpzError> {
+        let name: String = format!("arr_{}", self.positional);
+        self.add_array(&name, shape, fortran_order, data)?;
+        self.positional += 1usize;
+        Ok(())
+    }
+
+    fn add_member(&mut self, name: String, data: &[u8]) -> Result<(), NpzError> {
+        if self
+            .entries
+            .iter()
+            .any(|entry: &zip::Entry| entry.name == name)
+        {
+            return Err(NpzError::DuplicateMember(name));
+        }
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        let (method, compressed): (u16, std::borrow::Cow<[u8]>) = match self.compression {
+            Compression::Stored => (zip::METHOD_STORED, data.into()),
+            Compression::Deflated(level) => {
+                let mut encoder = flate2::write::DeflateEncoder::new(
+                    Vec::<u8>::new(),
+                    flate2::Compression::new(level),
+                );
+                encoder.write_all(data)?;
+                (zip::METHOD_DEFLATED, encoder.finish()?.into())
+            }
+        };
+        let flags: u16 = if name.is_ascii() {
+            0u16
+        } else {
+            zip::FLAG_UTF8
+        };
+        let entry = zip::Entry {
+            name,
+            flags,
+            method,
+            crc32: crc.sum(),
+            compressed_size: compressed.len() as u64,
+            uncompressed_size: data.len() as u64,
+            local_header_offset: self.offset,
+        };
+        let header: Vec<u8> = zip::prepare_local_file_header(&entry);
+        let inner: &mut W = self
+            .inner
+            .as_mut()
+            .expect("inner is taken only when finished");
+        inner.write_all(&header)?;
+        inner.write_all(&compressed)?;
+        self.offset += (header.len() + compressed.len()) as u64;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory and returns the sink.
+    pub fn finish(mut self) -> Result<W, NpzError> {
+        self.finalize()
+    }
+
+    fn finalize(&mut self) -> Result<W, NpzError> {
+        let mut inner: W = self
+            .inner
+            .take()
+            .expect("inner is taken only when finished");
+        let mut buf = Vec::<u8>::new();
+        for entry in self.entries.iter() {
+            buf.extend(zip::prepare_central_directory(entry));
+        }
+        buf.extend(zip::prepare_end_of_central_directory(
+            self.entries.len() as u64,
+            buf.len() as u64,
+            self.offset,
+        ));
+        inner.write_all(&buf)?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "writer")]
+impl<W: std::io::Write> Drop for NpzWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_none() || std::thread::panicking() {
+            return;
+        }
+        // errors can only be handled through finish
+        let _ = self.finalize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::zip;
+    use super::NpzReader;
+    #[cfg(feature = "writer")]
+    use super::{Compression, NpzWriter};
+    use crate::error::{NpzError, ReadArrayError};
+    use std::io::{Cursor, Write};
+
+    /// Assembles an archive by hand, optionally deflating members or recording them as ZIP64.
+    fn archive(members: &[(&str, &[u8])], deflate: bool, zip64: bool) -> Vec<u8> {
+        let mut buf = Vec::<u8>::new();
+        let mut central_directory = Vec::<u8>::new();
+        for (name, data) in members {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let (method, compressed): (u16, Vec<u8>) = if deflate {
+                let mut encoder = flate2::write::DeflateEncoder::new(
+                    Vec::<u8>::new(),
+                    flate2::Compression::default(),
+                );
+                encoder.write_all(data).unwrap();
+                (zip::METHOD_DEFLATED, encoder.finish().unwrap())
+            } else {
+                (zip::METHOD_STORED, data.to_vec())
+            };
+            let offset: u32 = buf.len() as u32;
+            buf.extend(zip::LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+            buf.extend(
+                [20u16, 0u16, method, 0u16, 0x21u16]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            );
+            buf.extend(crc.sum().to_le_bytes());
+            buf.extend((compressed.len() as u32).to_le_bytes());
+            buf.extend((data.len() as u32).to_le_bytes());
+            buf.extend((name.len() as u16).to_le_bytes());
+            buf.extend(0u16.to_le_bytes());
+            buf.extend(name.as_bytes());
+            buf.extend(&compressed);
+            let sizes: [u32; 2] = if zip64 {
+                [u32::MAX, u32::MAX]
+            } else {
+                [compressed.len() as u32, data.len() as u32]
+            };
+            let extra: Vec<u8> = if zip64 {
+                [zip::ZIP64_EXTRA_FIELD_ID, 16u16]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes())
+                    .chain((data.len() as u64).to_le_bytes())
+                    .chain((compressed.len() as u64).to_le_bytes())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let cd = &mut central_directory;
+            cd.extend(zip::CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            cd.extend(
+                [20u16, 20u16, 0u16, method, 0u16, 0x21u16]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            );
+            cd.extend(crc.sum().to_le_bytes());
+            cd.extend(sizes[0].to_le_bytes());
+            cd.extend(sizes[1].to_le_bytes());
+            cd.extend(
+                [name.len() as u16, extra.len() as u16, 0u16, 0u16, 0u16]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            );
+            cd.extend(0u32.to_le_bytes());
+            cd.extend(offset.to_le_bytes());
+            cd.extend(name.as_bytes());
+            cd.extend(&extra);
+        }
+        let start: u64 = buf.len() as u64;
+        let size: u64 = central_directory.len() as u64;
+        let count: u64 = members.len() as u64;
+        buf.extend(&central_directory);
+        if zip64 {
+            let record: u64 = buf.len() as u64;
+            buf.extend(zip::ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            buf.extend(44u64.to_le_bytes());
+            buf.extend([45u16, 45u16].iter().flat_map(|x| x.to_le_bytes()));
+            buf.extend([0u32, 0u32].iter().flat_map(|x| x.to_le_bytes()));
+            buf.extend(
+                [count, count, size, start]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            );
+            buf.extend(zip::ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE.to_le_bytes());
+            buf.extend(0u32.to_le_bytes());
+            buf.extend(record.to_le_bytes());
+            buf.extend(1u32.to_le_bytes());
+        }
+        buf.extend(zip::END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        buf.extend(
+            [0u16, 0u16, count as u16, count as u16]
+                .iter()
+                .flat_map(|x| x.to_le_bytes()),
+        );
+        buf.extend((size as u32).to_le_bytes());
+        buf.extend((start as u32).to_le_bytes());
+        buf.extend(0u16.to_le_bytes());
+        buf
+    }
+
+    /// Assembles a version 1.0 file by hand, so that the reader is tested without the writer.
+    fn npy<T: crate::Element>(shape: &str, data: &[T]) -> Vec<u8> {
+        let mut dict: String = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            T::dtype(),
+            shape
+        );
+        // the magic string, the version and HEADER_LEN take 10 bytes
+        while !(10usize + dict.len() + 1usize).is_multiple_of(64usize) {
+            dict.push(' ');
+        }
+        dict.push('\n');
+        let mut buf = Vec::<u8>::new();
+        buf.extend(b"\x93NUMPY\x01\x00");
+        buf.extend((dict.len() as u16).to_le_bytes());
+        buf.extend(dict.as_bytes());
+        for value in data {
+            let mut bytes = vec![0u8; T::dtype().itemsize];
+            value.to_bytes(&mut bytes, crate::dtype::ByteOrder::Little);
+            buf.extend(bytes);
+        }
+        buf
+    }
+
+    #[test]
+    fn read_members() {
+        let x: Vec<u8> = npy("(2, 2)", &[1f64, 2f64, 3f64, 4f64]);
+        let y: Vec<u8> = npy("(3,)", &[5i32, 6i32, 7i32]);
+        for (deflate, zip64) in [(false, false), (true, false), (false, true), (true, true)] {
+            let buf: Vec<u8> = archive(&[("x.npy", &x), ("arr_0.npy", &y)], deflate, zip64);
+            let mut npz = NpzReader::new(Cursor::new(buf)).unwrap();
+            assert_eq!(npz.names(), vec!["x", "arr_0"]);
+            let (header, data) = npz.read_array::<f64>("x").unwrap();
+            assert_eq!(header.shape, vec![2usize, 2usize]);
+            assert_eq!(data, vec![1f64, 2f64, 3f64, 4f64]);
+            let mut member = npz.by_name("arr_0.npy").unwrap();
+            assert_eq!(member.name(), "arr_0.npy");
+            let header = crate::read_header(&mut member).unwrap();
+            assert_eq!(
+                crate::read_array::<i32>(&mut member, &header).unwrap(),
+                vec![5i32, 6i32, 7i32]
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_archives() {
+        let x: Vec<u8> = npy("(3,)", &[1u8, 2u8, 3u8]);
+        let buf: Vec<u8> = archive(&[("x.npy", &x)], false, false);
+        let mut npz = NpzReader::new(Cursor::new(buf.clone())).unwrap();
+        assert!(matches!(
+            npz.by_name("y"),
+            Err(NpzError::MemberNotFound(name)) if name == "y"
+        ));
+        // corrupt the last byte of the payload
+        let mut corrupted: Vec<u8> = buf.clone();
+        corrupted[zip::SIZE_LOCAL_FILE_HEADER + "x.npy".len() + x.len() - 1] = 0u8;
+        let mut npz = NpzReader::new(Cursor::new(corrupted)).unwrap();
+        assert!(matches!(
+            npz.read_array::<u8>("x"),
+            Err(NpzError::ReadArrayFailed(ReadArrayError::Io(_)))
+        ));
+        assert!(matches!(
+            NpzReader::new(Cursor::new(x)),
+            Err(NpzError::EndOfCentralDirectoryNotFound)
+        ));
+    }
+
+    #[cfg(feature = "writer")]
+    #[test]
+    fn write_members() {
+        for compression in [Compression::Stored, Compression::Deflated(6u32)] {
+            let mut npz = NpzWriter::new(Vec::<u8>::new(), compression).unwrap();
+            npz.push_array(&[3usize], false, &[1f32, 2f32, 3f32])
+                .unwrap();
+            npz.add_array("速度", &[2usize, 0usize], false, &[] as &[i64])
+                .unwrap();
+            npz.push_array(&[2usize, 2usize], true, &[true, false, false, true])
+                .unwrap();
+            assert!(matches!(
+                npz.add_array("arr_0", &[0usize], false, &[] as &[u8]),
+                Err(NpzError::DuplicateMember(name)) if name == "arr_0.npy"
+            ));
+            let buf: Vec<u8> = npz.finish().unwrap();
+            // members are identical to standalone files
+            let mut standalone = Vec::<u8>::new();
+            crate::write_array(&mut standalone, &[3usize], false, &[1f32, 2f32, 3f32]).unwrap();
+            if compression == Compression::Stored {
+                assert!(buf
+                    .windows(standalone.len())
+                    .any(|w: &[u8]| w == standalone));
+            }
+            let mut npz = NpzReader::new(Cursor::new(buf)).unwrap();
+            assert_eq!(npz.names(), vec!["arr_0", "速度", "arr_1"]);
+            assert_eq!(
+                npz.read_array::<f32>("arr_0").unwrap().1,
+                vec![1f32, 2f32, 3f32]
+            );
+            let (header, data) = npz.read_array::<i64>("速度").unwrap();
+            assert_eq!(header.shape, vec![2usize, 0usize]);
+            assert!(data.is_empty());
+            let (header, data) = npz.read_array::<bool>("arr_1").unwrap();
+            assert!(header.fortran_order);
+            assert_eq!(data, vec![true, false, false, true]);
+        }
+    }
+
+    #[cfg(feature = "writer")]
+    #[test]
+    fn reproducible_output() {
+        let write = || -> Vec<u8> {
+            let mut npz = NpzWriter::new(Vec::<u8>::new(), Compression::Deflated(9u32)).unwrap();
+            npz.add_array("x", &[4usize], false, &[0u16; 4]).unwrap();
+            npz.finish().unwrap()
+        };
+        assert_eq!(write(), write());
+        assert!(matches!(
+            NpzWriter::new(Vec::<u8>::new(), Compression::Deflated(10u32)),
+            Err(NpzError::InvalidCompressionLevel(10u32))
+        ));
+    }
+
+    #[cfg(feature = "writer")]
+    #[test]
+    fn finish_on_drop() {
+        let mut buf = Vec::<u8>::new();
+        {
+            let mut npz = NpzWriter::new(&mut buf, Compression::Stored).unwrap();
+            npz.push_array(&[2usize], false, &[1u8, 2u8]).unwrap();
+        }
+        let mut npz = NpzReader::new(Cursor::new(buf)).unwrap();
+        assert_eq!(npz.read_array::<u8>("arr_0").unwrap().1, vec![1u8, 2u8]);
+    }
+
+    #[cfg(feature = "writer")]
+    #[test]
+    fn zip64_records() {
+        let entry = zip::Entry {
+            name: "x.npy".to_string(),
+            flags: 0u16,
+            method: zip::METHOD_STORED,
+            crc32: 0u32,
+            compressed_size: 1u64 << 32,
+            uncompressed_size: 1u64 << 32,
+            local_header_offset: 1u64 << 33,
+        };
+        let mut buf = vec![0u8; 128];
+        let start: u64 = buf.len() as u64;
+        let central_directory: Vec<u8> = zip::prepare_central_directory(&entry);
+        buf.extend(&central_directory);
+        buf.extend(zip::prepare_end_of_central_directory(
+            1u64,
+            central_directory.len() as u64,
+            start,
+        ));
+        assert_eq!(
+            zip::read_entries(&mut Cursor::new(buf)).unwrap(),
+            vec![entry]
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! The subset of the ZIP format used by `numpy.savez`,
+//! i.e. stored or deflated members without encryption, spanning or data descriptors.
+
+use crate::error::NpzError;
+
+pub const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+pub const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+pub const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064b50;
+pub const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+
+pub const SIZE_LOCAL_FILE_HEADER: usize = 30;
+#[cfg(feature = "writer")]
+pub const SIZE_CENTRAL_DIRECTORY: usize = 46;
+pub const SIZE_END_OF_CENTRAL_DIRECTORY: usize = 22;
+pub const SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY: usize = 56;
+pub const SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR: usize = 20;
+const MAX_COMMENT_LEN: usize = 0xffff;
+
+/// Header ID of the extra field holding 64-bit sizes and offsets.
+pub const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+pub const FLAG_ENCRYPTED: u16 = 1u16 << 0;
+#[cfg(feature = "writer")]
+pub const FLAG_UTF8: u16 = 1u16 << 11;
+
+pub const METHOD_STORED: u16 = 0;
+pub const METHOD_DEFLATED: u16 = 8;
+
+/// Versions needed to extract, i.e. 2.0 for deflate and 4.5 for ZIP64.
+#[cfg(feature = "writer")]
+const VERSION_DEFAULT: u16 = 20;
+#[cfg(feature = "writer")]
+const VERSION_ZIP64: u16 = 45;
+
+/// Fixed modification time (00:00:00) and date (1980-01-01) in the MS-DOS format,
+/// i.e. (hour, minute, second / 2) and (year - 1980, month, day) packed in 5/6/5 and 7/4/5 bits,
+/// so that the same arrays give the same archive.
+#[cfg(feature = "writer")]
+const DOS_TIME: u16 = 0;
+#[cfg(feature = "writer")]
+const DOS_DATE: u16 = 0x0021;
+
+/// A member of the archive, as described in the central directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub flags: u16,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub local_header_offset: u64,
+}
+
+/// Sequential little-endian access to the fields of a record.
+struct Fields<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Fields { buf, pos: 0usize }
+    }
+
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8], NpzError> {
+        let bytes: &[u8] = self
+            .buf
+            .get(self.pos..self.pos + size)
+            .ok_or_else(|| NpzError::Malformed("record is truncated".to_string()))?;
+        self.pos += size;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, NpzError> {
+        let bytes: &[u8] = self.bytes(2usize)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, NpzError> {
+        let bytes: &[u8] = self.bytes(4usize)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, NpzError> {
+        let bytes: &[u8] = self.bytes(8usize)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn signature(&mut self, expected: u32, offset: u64) -> Result<(), NpzError> {
+        let signature: u32 = self.u32()?;
+        if signature != expected {
+            return Err(NpzError::InvalidSignature(signature, offset));
+        }
+        Ok(())
+    }
+}
+
+fn read_at<R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+    offset: u64,
+    size: usize,
+) -> Result<Vec<u8>, NpzError> {
+    f.seek(std::io::SeekFrom::Start(offset))?;
+    let mut buf = vec![0u8; size];
+    f.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Location of the central directory, i.e. (number of entries, size, offset).
+fn find_central_directory<R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+) -> Result<(u64, u64, u64), NpzError> {
+    let end: u64 = f.seek(std::io::SeekFrom::End(0i64))?;
+    let tail_size: u64 = end.min((SIZE_END_OF_CENTRAL_DIRECTORY + MAX_COMMENT_LEN) as u64);
+    let tail_start: u64 = end - tail_size;
+    let tail: Vec<u8> = read_at(f, tail_start, tail_size as usize)?;
+    // the record is followed by a comment of arbitrary length, so search from the end
+    let signature: [u8; 4] = END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes();
+    let position: usize = (0usize..(tail.len() + 1).saturating_sub(SIZE_END_OF_CENTRAL_DIRECTORY))
+        .rev()
+        .find(|&i: &usize| tail[i..i + 4] == signature)
+        .ok_or(NpzError::EndOfCentralDirectoryNotFound)?;
+    let offset: u64 = tail_start + position as u64;
+    let mut fields = Fields::new(&tail[position..]);
+    fields.signature(END_OF_CENTRAL_DIRECTORY_SIGNATURE, offset)?;
+    let disk: u16 = fields.u16()?;
+    let central_directory_disk: u16 = fields.u16()?;
+    let _entries_on_disk: u16 = fields.u16()?;
+    let entries: u16 = fields.u16()?;
+    let size: u32 = fields.u32()?;
+    let start: u32 = fields.u32()?;
+    // ZIP64 records, if any, are placed just before the end of central directory
+    let locator_offset: Option<u64> =
+        offset.checked_sub(SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR as u64);
+    if let Some(locator_offset) = locator_offset {
+        let locator: Vec<u8> = read_at(
+            f,
+            locator_offset,
+            SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR,
+        )?;
+        let mut fields = Fields::new(&locator);
+        if fields.u32()? == ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE {
+            let _disk: u32 = fields.u32()?;
+            let record_offset: u64 = fields.u64()?;
+            let disks: u32 = fields.u32()?;
+            if 1u32 < disks {
+                return Err(NpzError::MultiDisk);
+            }
+            let record: Vec<u8> = read_at(f, record_offset, SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY)?;
+            let mut fields = Fields::new(&record);
+            fields.signature(ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE, record_offset)?;
+            let _record_size: u64 = fields.u64()?;
+            let _version_made_by: u16 = fields.u16()?;
+            let _version_needed: u16 = fields.u16()?;
+            let disk: u32 = fields.u32()?;
+            let central_directory_disk: u32 = fields.u32()?;
+            if disk != 0u32 || central_directory_disk != 0u32 {
+                return Err(NpzError::MultiDisk);
+            }
+            let _entries_on_disk: u64 = fields.u64()?;
+            let entries: u64 = fields.u64()?;
+            let size: u64 = fields.u64()?;
+            let start: u64 = fields.u64()?;
+            return Ok((entries, size, start));
+        }
+    }
+    if disk != 0u16 || central_directory_disk != 0u16 {
+        return Err(NpzError::MultiDisk);
+    }
+    Ok((entries as u64, size as u64, start as u64))
+}
+
+/// Replaces the 32-bit values saturated to `0xffffffff` by those in the ZIP64 extra field.
+fn apply_zip64_extra_field(extra: &[u8], entry: &mut Entry) -> Result<(), NpzError> {
+    let mut fields = Fields::new(extra);
+    while fields.pos < extra.len() {
+        let id: u16 = fields.u16()?;
+        let size: u16 = fields.u16()?;
+        let data: &[u8] = fields.bytes(size as usize)?;
+        if id != ZIP64_EXTRA_FIELD_ID {
+            continue;
+        }
+        let mut fields = Fields::new(data);
+        if entry.uncompressed_size == u32::MAX as u64 {
+            entry.uncompressed_size = fields.u64()?;
+        }
+        if entry.compressed_size == u32::MAX as u64 {
+            entry.compressed_size = fields.u64()?;
+        }
+        if entry.local_header_offset == u32::MAX as u64 {
+            entry.local_header_offset = fields.u64()?;
+        }
+    }
+    Ok(())
+}
+
+/// Lists the members recorded in the central directory.
+pub fn read_entries<R: std::io::Read + std::io::Seek>(f: &mut R) -> Result<Vec<Entry>, NpzError> {
+    let (count, size, start): (u64, u64, u64) = find_central_directory(f)?;
+    let end: u64 = f.seek(std::io::SeekFrom::End(0i64))?;
+    if end < start.saturating_add(size) {
+        return Err(NpzError::Malformed(
+            "central directory exceeds the archive".to_string(),
+        ));
+    }
+    let buf: Vec<u8> = read_at(f, start, size as usize)?;
+    let mut fields = Fields::new(&buf);
+    let mut entries = Vec::<Entry>::new();
+    for _ in 0u64..count {
+        let offset: u64 = start + fields.pos as u64;
+        fields.signature(CENTRAL_DIRECTORY_SIGNATURE, offset)?;
+        let _version_made_by: u16 = fields.u16()?;
+        let _version_needed: u16 = fields.u16()?;
+        let flags: u16 = fields.u16()?;
+        let method: u16 = fields.u16()?;
+        let _time: u16 = fields.u16()?;
+        let _date: u16 = fields.u16()?;
+        let crc32: u32 = fields.u32()?;
+        let compressed_size: u32 = fields.u32()?;
+        let uncompressed_size: u32 = fields.u32()?;
+        let name_len: u16 = fields.u16()?;
+        let extra_len: u16 = fields.u16()?;
+        let comment_len: u16 = fields.u16()?;
+        let _disk: u16 = fields.u16()?;
+        let _internal_attributes: u16 = fields.u16()?;
+        let _external_attributes: u32 = fields.u32()?;
+        let local_header_offset: u32 = fields.u32()?;
+        let name: &[u8] = fields.bytes(name_len as usize)?;
+        let extra: &[u8] = fields.bytes(extra_len as usize)?;
+        let _comment: &[u8] = fields.bytes(comment_len as usize)?;
+        // names without the UTF-8 flag are CP437, which agrees with UTF-8 for ASCII
+        let name: String = String::from_utf8_lossy(name).into_owned();
+        let mut entry = Entry {
+            name,
+            flags,
+            method,
+            crc32,
+            compressed_size: compressed_size as u64,
+            uncompressed_size: uncompressed_size as u64,
+            local_header_offset: local_header_offset as u64,
+        };
+        apply_zip64_extra_field(extra, &mut entry)?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Finds where the (possibly compressed) data of the entry starts.
+pub fn data_offset<R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+    entry: &Entry,
+) -> Result<u64, NpzError> {
+    let offset: u64 = entry.local_header_offset;
+    let buf: Vec<u8> = read_at(f, offset, SIZE_LOCAL_FILE_HEADER)?;
+    let mut fields = Fields::new(&buf);
+    fields.signature(LOCAL_FILE_HEADER_SIGNATURE, offset)?;
+    fields.bytes(22usize)?;
+    let name_len: u16 = fields.u16()?;
+    let extra_len: u16 = fields.u16()?;
+    Ok(offset + (SIZE_LOCAL_FILE_HEADER + name_len as usize + extra_len as usize) as u64)
+}
+
+/// Whether the sizes of the entry do not fit in the 32-bit fields.
+#[cfg(feature = "writer")]
+fn needs_zip64_sizes(entry: &Entry) -> bool {
+    u32::MAX as u64 <= entry.uncompressed_size || u32::MAX as u64 <= entry.compressed_size
+}
+
+/// Values moved to the ZIP64 extra field, whose 32-bit fields are saturated.
+#[cfg(feature = "writer")]
+fn zip64_values(entry: &Entry, with_offset: bool) -> Vec<u64> {
+    let mut values = Vec::<u64>::new();
+    if needs_zip64_sizes(entry) {
+        // the local file header requires both sizes
+        values.push(entry.uncompressed_size);
+        values.push(entry.compressed_size);
+    }
+    if with_offset && u32::MAX as u64 <= entry.local_header_offset {
+        values.push(entry.local_header_offset);
+    }
+    values
+}
+
+/// Compressed and uncompressed sizes stored in the 32-bit fields.
+#[cfg(feature = "writer")]
+fn sizes(entry: &Entry) -> [u32; 2] {
+    if needs_zip64_sizes(entry) {
+        [u32::MAX, u32::MAX]
+    } else {
+        [entry.compressed_size as u32, entry.uncompressed_size as u32]
+    }
+}
+
+#[cfg(feature = "writer")]
+fn saturate(value: u64) -> u32 {
+    value.min(u32::MAX as u64) as u32
+}
+
+#[cfg(feature = "writer")]
+fn push_zip64_extra_field(buf: &mut Vec<u8>, values: &[u64]) {
+    if values.is_empty() {
+        return;
+    }
+    buf.extend(ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+    buf.extend(((values.len() * 8usize) as u16).to_le_bytes());
+    for value in values {
+        buf.extend(value.to_le_bytes());
+    }
+}
+
+#[cfg(feature = "writer")]
+fn version_needed(values: &[u64]) -> u16 {
+    if values.is_empty() {
+        VERSION_DEFAULT
+    } else {
+        VERSION_ZIP64
+    }
+}
+
+/// Local file header followed by the name, which precedes the data.
+#[cfg(feature = "writer")]
+pub fn prepare_local_file_header(entry: &Entry) -> Vec<u8> {
+    let values: Vec<u64> = zip64_values(entry, false);
+    let mut extra = Vec::<u8>::new();
+    push_zip64_extra_field(&mut extra, &values);
+    let mut buf = Vec::<u8>::with_capacity(SIZE_LOCAL_FILE_HEADER + entry.name.len() + extra.len());
+    buf.extend(LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+    buf.extend(version_needed(&values).to_le_bytes());
+    buf.extend(entry.flags.to_le_bytes());
+    buf.extend(entry.method.to_le_bytes());
+    buf.extend(DOS_TIME.to_le_bytes());
+    buf.extend(DOS_DATE.to_le_bytes());
+    buf.extend(entry.crc32.to_le_bytes());
+    for size in sizes(entry) {
+        buf.extend(size.to_le_bytes());
+    }
+    buf.extend((entry.name.len() as u16).to_le_bytes());
+    buf.extend((extra.len() as u16).to_le_bytes());
+    buf.extend(entry.name.as_bytes());
+    buf.extend(extra);
+    buf
+}
+
+/// Central directory record of the entry.
+#[cfg(feature = "writer")]
+pub fn prepare_central_directory(entry: &Entry) -> Vec<u8> {
+    let values: Vec<u64> = zip64_values(entry, true);
+    let mut extra = Vec::<u8>::new();
+    push_zip64_extra_field(&mut extra, &values);
+    let version: u16 = version_needed(&values);
+    let mut buf = Vec::<u8>::with_capacity(SIZE_CENTRAL_DIRECTORY + entry.name.len() + extra.len());
+    buf.extend(CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    // made by MS-DOS, i.e. no file attributes
+    buf.extend(version.to_le_bytes());
+    buf.extend(version.to_le_bytes());
+    buf.extend(entry.flags.to_le_bytes());
+    buf.extend(entry.method.to_le_bytes());
+    buf.extend(DOS_TIME.to_le_bytes());
+    buf.extend(DOS_DATE.to_le_bytes());
+    buf.extend(entry.crc32.to_le_bytes());
+    for size in sizes(entry) {
+        buf.extend(size.to_le_bytes());
+    }
+    buf.extend((entry.name.len() as u16).to_le_bytes());
+    buf.extend((extra.len() as u16).to_le_bytes());
+    // comment length, disk number and internal attributes
+    buf.extend([0u8; 6]);
+    // external attributes
+    buf.extend([0u8; 4]);
+    buf.extend(saturate(entry.local_header_offset).to_le_bytes());
+    buf.extend(entry.name.as_bytes());
+    buf.extend(extra);
+    buf
+}
+
+/// End of central directory record, preceded by the ZIP64 records if needed.
+#[cfg(feature = "writer")]
+pub fn prepare_end_of_central_directory(count: u64, size: u64, start: u64) -> Vec<u8> {
+    let mut buf = Vec::<u8>::new();
+    let zip64: bool =
+        u16::MAX as u64 <= count || u32::MAX as u64 <= size || u32::MAX as u64 <= start;
+    if zip64 {
+        let record: u64 = start + size;
+        buf.extend(ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        // size of the remaining record
+        buf.extend(((SIZE_ZIP64_END_OF_CENTRAL_DIRECTORY - 12usize) as u64).to_le_bytes());
+        buf.extend(VERSION_ZIP64.to_le_bytes());
+        buf.extend(VERSION_ZIP64.to_le_bytes());
+        buf.extend([0u8; 8]);
+        buf.extend(count.to_le_bytes());
+        buf.extend(count.to_le_bytes());
+        buf.extend(size.to_le_bytes());
+        buf.extend(start.to_le_bytes());
+        buf.extend(ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE.to_le_bytes());
+        buf.extend(0u32.to_le_bytes());
+        buf.extend(record.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+    }
+    let count: u16 = count.min(u16::MAX as u64) as u16;
+    buf.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    buf.extend([0u8; 4]);
+    buf.extend(count.to_le_bytes());
+    buf.extend(count.to_le_bytes());
+    buf.extend(saturate(size).to_le_bytes());
+    buf.extend(saturate(start).to_le_bytes());
+    // comment length
+    buf.extend([0u8; 2]);
+    buf
+}
@@ -1,18 +1,19 @@
 mod dict;
 pub mod error;
+mod literal;
 
 use crate::consts::{
     HEADER_BLOCK_SIZE, MAGIC_STRING, MINOR_VERSION, SIZE_HEADER_LEN, SIZE_MAJOR_VERSION,
     SIZE_MINOR_VERSION,
 };
-use crate::error::ReadHeaderError;
+use crate::error::{ReadArrayError, ReadHeaderError};
 use std::io::Read;
 
-fn read_exact(f: &mut std::fs::File, buf: &mut [u8]) -> Result<(), ReadHeaderError> {
+fn read_exact<R: Read>(f: &mut R, buf: &mut [u8]) -> Result<(), ReadHeaderError> {
     f.read_exact(buf).map_err(ReadHeaderError::Io)
 }
 
-pub fn check_magic_string(f: &mut std::fs::File) -> Result<(), ReadHeaderError> {
+pub fn check_magic_string<R: Read>(f: &mut R) -> Result<(), ReadHeaderError> {
     let mut buf = [0u8; MAGIC_STRING.len()];
     read_exact(f, &mut buf)?;
     if MAGIC_STRING != buf {
@@ -21,7 +22,7 @@ pub fn check_magic_string(f: &mut std::fs::File) -> Result<(), ReadHeaderError>
     Ok(())
 }
 
-pub fn fetch_major_version(f: &mut std::fs::File) -> Result<u8, ReadHeaderError> {
+pub fn fetch_major_version<R: Read>(f: &mut R) -> Result<u8, ReadHeaderError> {
     let mut buf = [0u8; SIZE_MAJOR_VERSION];
     read_exact(f, &mut buf)?;
     let major_version = buf[0];
@@ -31,7 +32,7 @@ pub fn fetch_major_version(f: &mut std::fs::File) -> Result<u8, ReadHeaderError>
     Ok(major_version)
 }
 
-pub fn check_minor_version(f: &mut std::fs::File) -> Result<(), ReadHeaderError> {
+pub fn check_minor_version<R: Read>(f: &mut R) -> Result<(), ReadHeaderError> {
     let mut buf = [0u8; SIZE_MINOR_VERSION];
     read_exact(f, &mut buf)?;
     let minor_version = buf[0];
@@ -41,10 +42,7 @@ pub fn check_minor_version(f: &mut std::fs::File) -> Result<(), ReadHeaderError>
     Ok(())
 }
 
-pub fn fetch_header_len(
-    f: &mut std::fs::File,
-    major_version: u8,
-) -> Result<usize, ReadHeaderError> {
+pub fn fetch_header_len<R: Read>(f: &mut R, major_version: u8) -> Result<usize, ReadHeaderError> {
     let header_len: usize = if major_version == 1u8 {
         let mut buf = [0u8; SIZE_HEADER_LEN[0]];
         read_exact(f, &mut buf)?;
@@ -71,20 +69,85 @@ pub fn fetch_header_len(
         }
         _ => return Err(ReadHeaderError::InvalidMajorVersion(major_version)),
     };
-    if header_size % HEADER_BLOCK_SIZE != 0usize {
+    if !header_size.is_multiple_of(HEADER_BLOCK_SIZE) {
         return Err(ReadHeaderError::InvalidHeaderSize(header_size));
     }
     Ok(header_len)
 }
 
-pub fn fetch_header(
-    f: &mut std::fs::File,
+/// Reads the header, returning it together with the major version and HEADER_LEN.
+pub fn read_header_parts<R: Read>(
+    f: &mut R,
+) -> Result<(crate::Header, u8, usize), ReadHeaderError> {
+    check_magic_string(f)?;
+    let major_version: u8 = fetch_major_version(f)?;
+    check_minor_version(f)?;
+    let header_len: usize = fetch_header_len(f, major_version)?;
+    let header: crate::Header = fetch_header(f, header_len, major_version)?;
+    Ok((header, major_version, header_len))
+}
+
+/// Computes the layout of the file, checking that the sizes do not overflow.
+pub fn prepare_header_info(
+    header: crate::Header,
+    major_version: u8,
+    header_len: usize,
+) -> Result<crate::HeaderInfo, ReadHeaderError> {
+    let size_header_len: usize =
+        SIZE_HEADER_LEN[if major_version == 1u8 { 0usize } else { 1usize }];
+    let data_offset: usize =
+        MAGIC_STRING.len() + SIZE_MAJOR_VERSION + SIZE_MINOR_VERSION + size_header_len + header_len;
+    let itemsize: usize = header.descr.itemsize();
+    let count: Option<usize> = header
+        .shape
+        .iter()
+        .try_fold(1usize, |count: usize, &n: &usize| count.checked_mul(n));
+    let (count, payload_size): (usize, usize) =
+        match count.and_then(|count: usize| Some((count, count.checked_mul(itemsize)?))) {
+            Some(sizes) => sizes,
+            None => return Err(ReadHeaderError::SizeOverflow(header.shape)),
+        };
+    Ok(crate::HeaderInfo {
+        header,
+        major_version,
+        header_len,
+        data_offset,
+        itemsize,
+        count,
+        payload_size,
+    })
+}
+
+pub fn fetch_header<R: Read>(
+    f: &mut R,
     header_len: usize,
+    major_version: u8,
 ) -> Result<crate::Header, ReadHeaderError> {
     let header: crate::Header = {
         let mut buf = vec![0u8; header_len];
         read_exact(f, &mut buf)?;
-        dict::parse(&buf)?
+        dict::parse(&buf, major_version)?
     };
     Ok(header)
 }
+
+/// Reads exactly `size` bytes, reporting how many bytes are found if the stream ends earlier.
+pub fn read_payload<R: Read>(f: &mut R, size: usize) -> Result<Vec<u8>, ReadArrayError> {
+    let mut buf = Vec::<u8>::with_capacity(size);
+    read_payload_into(f, size, &mut buf)?;
+    Ok(buf)
+}
+
+/// Reads exactly `size` bytes as [`read_payload`] does, reusing the allocation of `buf`.
+pub fn read_payload_into<R: Read>(
+    f: &mut R,
+    size: usize,
+    buf: &mut Vec<u8>,
+) -> Result<(), ReadArrayError> {
+    buf.clear();
+    f.by_ref().take(size as u64).read_to_end(buf)?;
+    if buf.len() != size {
+        return Err(ReadArrayError::Truncated(size, buf.len()));
+    }
+    Ok(())
+}
@@ -1,77 +1,108 @@
+use crate::dtype::{DType, Descr, Field};
 use crate::reader::error::ParseError;
+use crate::reader::literal::{self, Value};
 
-fn find_value(haystack: &str, key: &str, value: &str) -> Result<String, ParseError> {
-    let pattern = format!(r#"('{key}'|"{key}")\s*:\s*{value}"#);
-    let regex = regex::Regex::new(&pattern).unwrap();
-    let pairs: Vec<&str> = regex
-        .captures_iter(haystack)
-        .map(|c| c.extract())
-        .map(|(_, [_key, value])| value)
+fn find_value<'a>(dict: &'a [(Value, Value)], key: &str) -> Result<&'a Value, ParseError> {
+    let values: Vec<&Value> = dict
+        .iter()
+        .filter(|(k, _)| matches!(k, Value::Str(k) if k == key))
+        .map(|(_, value)| value)
         .collect();
-    match pairs.len() {
+    match values.len() {
         0 => Err(ParseError::missing_key_value_pairs(key)),
-        1 => {
-            let pair = &pairs[0];
-            Ok(pair.to_string())
-        }
+        1 => Ok(values[0]),
         _ => Err(ParseError::multiple_key_value_pairs(key)),
     }
 }
 
-fn fetch_descr(buf: &str) -> Result<String, ParseError> {
-    // TODO: not limited to strings but can accept general dtype.descr
-    let descr: String = find_value(buf, "descr", r#"('.+?'|".+?")"#)?;
-    Ok(descr.to_string())
-}
-
-fn fetch_fortran_order(buf: &str) -> Result<bool, ParseError> {
-    let fortran_order: String = find_value(buf, "fortran_order", r#"(True|False)"#)?;
-    match fortran_order.as_str() {
-        "True" => Ok(true),
-        "False" => Ok(false),
-        _ => Err(ParseError::invalid_bool_found_in_string(&fortran_order)),
+fn to_shape(value: &Value) -> Option<Vec<usize>> {
+    let to_usize = |value: &Value| -> Option<usize> {
+        match value {
+            Value::Int(value) => usize::try_from(*value).ok(),
+            _ => None,
+        }
+    };
+    match value {
+        Value::Tuple(values) => values.iter().map(to_usize).collect(),
+        // older versions of this crate wrote one-dimensional shapes as "(3)"
+        Value::Int(_) => to_usize(value).map(|value: usize| vec![value]),
+        _ => None,
     }
 }
 
-fn fetch_shape(buf: &str) -> Result<Vec<usize>, ParseError> {
-    let shape: String = find_value(buf, "shape", r#"\(([^)]*)\)"#)?;
-    // remove all white spaces for later convenience
-    let shape: String = shape
-        .chars()
-        .filter(|character: &char| !character.is_whitespace())
-        .collect::<String>();
-    // early return for scalars
-    if shape.is_empty() {
-        return Ok(Vec::<usize>::new());
-    }
-    // eliminate trailing comma if any
-    let shape: String = {
-        let ends_with_comma = shape.ends_with(',');
-        if ends_with_comma {
-            let length = shape.len();
-            shape[0..length - 1].to_string()
-        } else {
-            shape[..].to_string()
+fn to_descr(value: &Value) -> Result<Descr, ParseError> {
+    let fields: &[Value] = match value {
+        Value::Str(dtype) => {
+            let dtype: DType = dtype.parse::<DType>().map_err(ParseError::InvalidDType)?;
+            return Ok(Descr::Simple(dtype));
         }
+        Value::List(fields) => fields,
+        _ => return Err(ParseError::invalid_descr(value)),
     };
-    // finally split with a comma delimiter
-    // expect IntErrorKind::Empty for invalid comma use
-    let shape: Result<Vec<usize>, std::num::ParseIntError> = shape
-        .split(',')
-        .map(|s| s.trim().parse::<usize>())
-        .collect();
-    match shape {
-        Ok(shape) => Ok(shape),
-        Err(e) => Err(ParseError::ParseInt(e)),
+    // each field is either (name, descr) or (name, descr, shape)
+    let fields: Vec<Field> = fields
+        .iter()
+        .map(|field: &Value| -> Result<Field, ParseError> {
+            let (name, descr, shape) = match field {
+                Value::Tuple(items) => match items.as_slice() {
+                    [Value::Str(name), descr] => (name, descr, Some(Vec::new())),
+                    [Value::Str(name), descr, shape] => (name, descr, to_shape(shape)),
+                    _ => return Err(ParseError::invalid_descr(field)),
+                },
+                _ => return Err(ParseError::invalid_descr(field)),
+            };
+            let shape: Vec<usize> = shape.ok_or_else(|| ParseError::invalid_descr(field))?;
+            Ok(Field {
+                name: name.to_string(),
+                descr: to_descr(descr)?,
+                shape,
+            })
+        })
+        .collect::<Result<Vec<Field>, ParseError>>()?;
+    let descr = Descr::Record(fields);
+    descr.validate().map_err(ParseError::InvalidDType)?;
+    Ok(descr)
+}
+
+fn fetch_descr(dict: &[(Value, Value)]) -> Result<Descr, ParseError> {
+    let descr: &Value = find_value(dict, "descr")?;
+    to_descr(descr)
+}
+
+fn fetch_fortran_order(dict: &[(Value, Value)]) -> Result<bool, ParseError> {
+    let fortran_order: &Value = find_value(dict, "fortran_order")?;
+    match fortran_order {
+        Value::Bool(fortran_order) => Ok(*fortran_order),
+        _ => Err(ParseError::invalid_bool_found_in_string(
+            &fortran_order.to_string(),
+        )),
     }
 }
 
-pub fn parse(buf: &[u8]) -> Result<crate::Header, ParseError> {
+fn fetch_shape(dict: &[(Value, Value)]) -> Result<Vec<usize>, ParseError> {
+    let value: &Value = find_value(dict, "shape")?;
+    to_shape(value).ok_or_else(|| ParseError::invalid_shape(value))
+}
+
+/// Decodes the header text, which is in latin-1 for versions 1.0 and 2.0 and in utf-8 for 3.0.
+fn decode(buf: &[u8], major_version: u8) -> Result<String, ParseError> {
+    if major_version < 3u8 {
+        return Ok(buf.iter().map(|&byte: &u8| char::from(byte)).collect());
+    }
     let buf: &str = std::str::from_utf8(buf)
         .map_err(|error: std::str::Utf8Error| ParseError::InvalidUTF8(error))?;
-    let descr: String = fetch_descr(buf)?;
-    let fortran_order: bool = fetch_fortran_order(buf)?;
-    let shape: Vec<usize> = fetch_shape(buf)?;
+    Ok(buf.to_string())
+}
+
+pub fn parse(buf: &[u8], major_version: u8) -> Result<crate::Header, ParseError> {
+    let buf: String = decode(buf, major_version)?;
+    let dict: Vec<(Value, Value)> = match literal::parse(&buf)? {
+        Value::Dict(dict) => dict,
+        value => return Err(ParseError::not_a_dictionary(&value)),
+    };
+    let descr: Descr = fetch_descr(&dict)?;
+    let fortran_order: bool = fetch_fortran_order(&dict)?;
+    let shape: Vec<usize> = fetch_shape(&dict)?;
     let header = crate::Header {
         descr,
         fortran_order,
@@ -82,150 +113,246 @@ pub fn parse(buf: &[u8]) -> Result<crate::Header, ParseError> {
 
 #[cfg(test)]
 mod tests {
-    use super::{fetch_descr, fetch_fortran_order, fetch_shape};
+    use super::{fetch_descr, fetch_fortran_order, fetch_shape, parse};
+    use crate::dtype::{ByteOrder, DType, Descr, Field, Kind};
+    use crate::error::DTypeError;
     use crate::reader::error::ParseError;
+    use crate::reader::literal::{self, Value};
+
+    fn dict(buf: &str) -> Vec<(Value, Value)> {
+        match literal::parse(buf) {
+            Ok(Value::Dict(dict)) => dict,
+            _ => panic!("not a dictionary: {}", buf),
+        }
+    }
 
     #[test]
     fn single_quotations() {
-        let haystack = r#"'descr': '<i8', 'fortran_order': False, 'shape': (3, 5), "#;
-        assert_eq!(fetch_descr(haystack), Ok(r#"'<i8'"#.to_string()));
-        assert_eq!(fetch_fortran_order(haystack), Ok(false));
-        assert_eq!(fetch_shape(haystack), Ok([3usize, 5usize].to_vec()));
+        let haystack = dict(r#"{'descr': '<i8', 'fortran_order': False, 'shape': (3, 5), }"#);
+        assert_eq!(
+            fetch_descr(&haystack),
+            Ok(Descr::Simple(DType::new(
+                ByteOrder::Little,
+                Kind::Int,
+                8usize
+            )))
+        );
+        assert_eq!(fetch_fortran_order(&haystack), Ok(false));
+        assert_eq!(fetch_shape(&haystack), Ok([3usize, 5usize].to_vec()));
     }
 
     #[test]
     fn double_quotations() {
-        let haystack = r#""descr": "<i8", "fortran_order": False, "shape": (3, 5), "#;
-        assert_eq!(fetch_descr(haystack), Ok(r#""<i8""#.to_string()));
-        assert_eq!(fetch_fortran_order(haystack), Ok(false));
-        assert_eq!(fetch_shape(haystack), Ok([3usize, 5usize].to_vec()));
+        let haystack = dict(r#"{"descr": "<i8", "fortran_order": False, "shape": (3, 5), }"#);
+        assert_eq!(
+            fetch_descr(&haystack),
+            Ok(Descr::Simple(DType::new(
+                ByteOrder::Little,
+                Kind::Int,
+                8usize
+            )))
+        );
+        assert_eq!(fetch_fortran_order(&haystack), Ok(false));
+        assert_eq!(fetch_shape(&haystack), Ok([3usize, 5usize].to_vec()));
     }
 
     #[test]
     fn inconsistent_quotations() {
+        assert!(parse(br#"{"descr':"<i8"}"#, 1u8).is_err());
+        assert!(parse(br#"{'descr":"<i8"}"#, 1u8).is_err());
+    }
+
+    #[test]
+    fn keys_in_nested_values() {
+        // key names appearing in nested values must not be confused with the keys
+        let buf =
+            br#"{'descr': '<f8', 'fortran_order': False, 'shape': (2,), 'x': {'shape': (3,)}}"#;
+        let header = parse(buf, 1u8).unwrap();
+        assert_eq!(header.shape, vec![2]);
+    }
+
+    #[test]
+    fn encodings() {
+        // field names are in latin-1 for versions 1.0 and 2.0
+        let buf = b"{'descr': [('\xe9', '<f8')], 'fortran_order': False, 'shape': (), }";
+        for major_version in [1u8, 2u8] {
+            let header = parse(buf, major_version).unwrap();
+            assert_eq!(header.descr.to_string(), "[('\u{e9}', '<f8')]");
+        }
+        assert!(matches!(parse(buf, 3u8), Err(ParseError::InvalidUTF8(_))));
+        // and in utf-8 for version 3.0
+        let buf = "{'descr': [('速度', '<f8')], 'fortran_order': False, 'shape': (), }";
+        let header = parse(buf.as_bytes(), 3u8).unwrap();
+        assert_eq!(header.descr.to_string(), "[('速度', '<f8')]");
+    }
+
+    #[test]
+    fn fetch_descr_normal() {
+        let expected = Descr::Simple(DType::new(ByteOrder::Little, Kind::Float, 8usize));
         assert_eq!(
-            fetch_descr(r#""descr':"<i8""#),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict(r#"{'descr':'<f8'}"#)),
+            Ok(expected.clone())
         );
         assert_eq!(
-            fetch_descr(r#"'descr":"<i8""#),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict(r#"{'descr' :"<f8"}"#)),
+            Ok(expected.clone())
+        );
+        assert_eq!(fetch_descr(&dict(r#"{"descr": '<f8'}"#)), Ok(expected));
+        assert_eq!(
+            fetch_descr(&dict(r#"{'descr': '>u2'}"#)),
+            Ok(Descr::Simple(DType::new(
+                ByteOrder::Big,
+                Kind::UInt,
+                2usize
+            )))
         );
     }
 
     #[test]
-    fn fetch_descr_normal() {
-        assert_eq!(fetch_descr(r#"'descr':'<f8'"#), Ok(r#"'<f8'"#.to_string()));
-        assert_eq!(fetch_descr(r#"'descr' :"<f8""#), Ok(r#""<f8""#.to_string()));
-        assert_eq!(fetch_descr(r#""descr": '<f8'"#), Ok(r#"'<f8'"#.to_string()));
+    fn fetch_descr_record() {
+        let simple = |descr: &str| Descr::Simple(descr.parse::<DType>().unwrap());
+        let buf = "{'descr': [('shape', '<f8', (3,)), ('', '|V4'), ('n', [('a', '>i2')])], 'fortran_order': False, 'shape': (10,), }";
+        let header = parse(buf.as_bytes(), 1u8).unwrap();
         assert_eq!(
-            fetch_descr(r#""descr" : "<f8""#),
-            Ok(r#""<f8""#.to_string())
+            header.descr,
+            Descr::Record(vec![
+                Field {
+                    name: "shape".to_string(),
+                    descr: simple("<f8"),
+                    shape: vec![3usize],
+                },
+                Field {
+                    name: "".to_string(),
+                    descr: simple("|V4"),
+                    shape: vec![],
+                },
+                Field {
+                    name: "n".to_string(),
+                    descr: Descr::Record(vec![Field {
+                        name: "a".to_string(),
+                        descr: simple(">i2"),
+                        shape: vec![],
+                    }]),
+                    shape: vec![],
+                },
+            ])
         );
+        assert_eq!(header.descr.itemsize(), 24 + 4 + 2);
+        assert_eq!(header.shape, vec![10usize]);
     }
 
     #[test]
     fn fetch_fortran_order_normal() {
-        assert_eq!(fetch_fortran_order(r#"'fortran_order':True"#), Ok(true));
-        assert_eq!(fetch_fortran_order(r#"'fortran_order' :False"#), Ok(false));
-        assert_eq!(fetch_fortran_order(r#""fortran_order": True"#), Ok(true));
-        assert_eq!(fetch_fortran_order(r#""fortran_order" : False"#), Ok(false));
+        assert_eq!(
+            fetch_fortran_order(&dict(r#"{'fortran_order':True}"#)),
+            Ok(true)
+        );
+        assert_eq!(
+            fetch_fortran_order(&dict(r#"{'fortran_order' :False}"#)),
+            Ok(false)
+        );
+        assert_eq!(
+            fetch_fortran_order(&dict(r#"{"fortran_order": True}"#)),
+            Ok(true)
+        );
+        assert_eq!(
+            fetch_fortran_order(&dict(r#"{"fortran_order" : False}"#)),
+            Ok(false)
+        );
     }
 
     #[test]
     fn fetch_shape_normal() {
-        assert_eq!(fetch_shape("'shape':()"), Ok(Vec::new()));
-        assert_eq!(fetch_shape("'shape':( )"), Ok(Vec::new()));
-        assert_eq!(fetch_shape("'shape': (2)"), Ok(vec![2]));
-        assert_eq!(fetch_shape("'shape' :(2,)"), Ok(vec![2]));
-        assert_eq!(fetch_shape("'shape': (42)"), Ok(vec![42]));
-        assert_eq!(fetch_shape("'shape' :(42,)"), Ok(vec![42]));
-        assert_eq!(fetch_shape("'shape' : (1, 2,  3)"), Ok(vec![1, 2, 3]));
+        assert_eq!(fetch_shape(&dict("{'shape':()}")), Ok(Vec::new()));
+        assert_eq!(fetch_shape(&dict("{'shape':( )}")), Ok(Vec::new()));
+        assert_eq!(fetch_shape(&dict("{'shape': (2)}")), Ok(vec![2]));
+        assert_eq!(fetch_shape(&dict("{'shape' :(2,)}")), Ok(vec![2]));
+        assert_eq!(fetch_shape(&dict("{'shape': (42)}")), Ok(vec![42]));
+        assert_eq!(fetch_shape(&dict("{'shape' :(42,)}")), Ok(vec![42]));
+        assert_eq!(
+            fetch_shape(&dict("{'shape' : (1, 2,  3)}")),
+            Ok(vec![1, 2, 3])
+        );
     }
 
     #[test]
     fn fetch_descr_corner() {
         assert_eq!(
-            fetch_descr("'descr':<i8"),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict("{'descr':None}")),
+            Err(ParseError::InvalidDescr("None".to_string()))
         );
         assert_eq!(
-            fetch_descr("'descr':,"),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict("{'descr':'hoge'}")),
+            Err(ParseError::InvalidDType(DTypeError::unknown_kind("hoge")))
         );
         assert_eq!(
-            fetch_descr("'descr': ,"),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict("{'descr':'<f3'}")),
+            Err(ParseError::InvalidDType(DTypeError::invalid_itemsize(
+                "<f3"
+            )))
         );
+        for input in [
+            "{'descr': [('x',)]}",
+            "{'descr': [('x', '<f8', (-1,))]}",
+            "{'descr': [(('title', 'x'), '<f8')]}",
+            "{'descr': ['<f8']}",
+        ] {
+            assert!(matches!(
+                fetch_descr(&dict(input)),
+                Err(ParseError::InvalidDescr(_))
+            ));
+        }
         assert_eq!(
-            fetch_descr(r#"descr:"<i8""#),
-            Err(ParseError::missing_key_value_pairs("descr"))
+            fetch_descr(&dict("{'descr': [('x', '<f8'), ('x', '<i4')]}")),
+            Err(ParseError::InvalidDType(DTypeError::duplicate_field_name(
+                "x"
+            )))
         );
         assert_eq!(
-            fetch_descr(r#":"<i8""#),
+            fetch_descr(&dict("{'desc': '<i8'}")),
             Err(ParseError::missing_key_value_pairs("descr"))
         );
         assert_eq!(
-            fetch_descr("'descr': '<f8', 'descr': '<i8'"),
+            fetch_descr(&dict("{'descr': '<f8', 'descr': '<i8'}")),
             Err(ParseError::multiple_key_value_pairs("descr"))
         );
+        assert!(parse(b"{'descr':<i8}", 1u8).is_err());
+        assert!(parse(b"{'descr':,}", 1u8).is_err());
+        assert!(parse(br#"{descr:"<i8"}"#, 1u8).is_err());
     }
 
     #[test]
     fn fetch_fortran_order_corner() {
         assert_eq!(
-            fetch_fortran_order(r#""fortran_order':True"#),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
-        );
-        assert_eq!(
-            fetch_fortran_order(r#"'fortran_order":False"#),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
-        );
-        assert_eq!(
-            fetch_fortran_order(r#"'fortran_order':"True""#),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
-        );
-        assert_eq!(
-            fetch_fortran_order("'fortran_order':,"),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
-        );
-        assert_eq!(
-            fetch_fortran_order("'fortran_order': ,"),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
-        );
-        assert_eq!(
-            fetch_fortran_order(r#"fortran_order:"<i8""#),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
+            fetch_fortran_order(&dict(r#"{'fortran_order':"True"}"#)),
+            Err(ParseError::invalid_bool_found_in_string("'True'"))
         );
         assert_eq!(
-            fetch_fortran_order(r#":False"#),
-            Err(ParseError::missing_key_value_pairs("fortran_order"))
+            fetch_fortran_order(&dict("{'fortran_order':1}")),
+            Err(ParseError::invalid_bool_found_in_string("1"))
         );
         assert_eq!(
-            fetch_fortran_order("'fortran_order': True, 'fortran_order': False"),
+            fetch_fortran_order(&dict("{'fortran_order': True, 'fortran_order': False}")),
             Err(ParseError::multiple_key_value_pairs("fortran_order"))
         );
+        assert!(parse(b"{'fortran_order': ,}", 1u8).is_err());
     }
 
     #[test]
     fn fetch_shape_corner() {
-        for input in ["'shape': (not_a_number)", "'shape': (1, two, 3)"] {
-            let shape = fetch_shape(input);
-            assert!(shape.is_err());
-            if let Err(ParseError::ParseInt(e)) = shape {
-                assert_eq!(e.kind(), &std::num::IntErrorKind::InvalidDigit);
-            } else {
-                panic!("unreachable");
-            }
+        for (input, shape) in [
+            ("{'shape': (1, -2, 3)}", "(1, -2, 3)"),
+            ("{'shape': [1, 2]}", "[1, 2]"),
+            ("{'shape': (1, '2')}", "(1, '2')"),
+            ("{'shape': ((1,),)}", "((1,),)"),
+        ] {
+            assert_eq!(
+                fetch_shape(&dict(input)),
+                Err(ParseError::InvalidShape(shape.to_string()))
+            );
         }
-        for input in ["'shape': (,)", "'shape': (,,)", "'shape': (0,,2)"] {
-            let shape = fetch_shape(input);
-            assert!(shape.is_err());
-            if let Err(ParseError::ParseInt(e)) = shape {
-                assert_eq!(e.kind(), &std::num::IntErrorKind::Empty);
-            } else {
-                panic!("unreachable");
-            }
+        for input in ["{'shape': (,)}", "{'shape': (,,)}", "{'shape': (0,,2)}"] {
+            assert!(literal::parse(input).is_err());
         }
     }
 }
@@ -1,10 +1,24 @@
+use crate::error::DTypeError;
+use crate::reader::literal::Value;
+
 #[derive(Debug, PartialEq)]
 pub enum ParseError {
     InvalidUTF8(std::str::Utf8Error),
+    UnexpectedEndOfInput,
+    UnexpectedCharacter(char, usize),
+    UnterminatedString(usize),
+    InvalidEscapeSequence(usize),
+    TooDeeplyNested(usize),
+    UnknownIdentifier(String),
+    NotADictionary(String),
     MissingKeyValuePairs(String),
     MultipleKeyValuePairs(String),
+    InvalidDescr(String),
+    InvalidDType(DTypeError),
     InvalidBoolFoundInString(String),
+    InvalidShape(String),
     ParseInt(std::num::ParseIntError),
+    InvalidIntegerLiteral(String),
 }
 
 impl std::error::Error for ParseError {}
@@ -15,23 +29,76 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidUTF8(error) => {
                 write!(f, "Invalid utf-8 characters are found: '{}'", error)
             }
+            ParseError::UnexpectedEndOfInput => {
+                write!(f, "Unexpected end of input")
+            }
+            ParseError::UnexpectedCharacter(character, position) => {
+                write!(
+                    f,
+                    "Unexpected character '{}' is found at {}",
+                    character, position
+                )
+            }
+            ParseError::UnterminatedString(position) => {
+                write!(f, "String starting at {} is not terminated", position)
+            }
+            ParseError::InvalidEscapeSequence(position) => {
+                write!(f, "Invalid escape sequence is found at {}", position)
+            }
+            ParseError::TooDeeplyNested(position) => {
+                write!(f, "Containers are nested too deeply at {}", position)
+            }
+            ParseError::UnknownIdentifier(identifier) => {
+                write!(f, "Unknown identifier: '{}'", identifier)
+            }
+            ParseError::NotADictionary(value) => {
+                write!(f, "Header is not a dictionary: {}", value)
+            }
             ParseError::MissingKeyValuePairs(key) => {
                 write!(f, "Key-value pair is missing: '{}'", key)
             }
             ParseError::MultipleKeyValuePairs(key) => {
                 write!(f, "Multiple key-value pairs are found: '{}'", key)
             }
+            ParseError::InvalidDescr(value) => {
+                write!(f, "Invalid descr: {}", value)
+            }
+            ParseError::InvalidDType(error) => {
+                write!(f, "Invalid data type: {}", error)
+            }
             ParseError::InvalidBoolFoundInString(invalid_value) => {
                 write!(f, "Invalid boolean found in string: '{}'", invalid_value)
             }
+            ParseError::InvalidShape(value) => {
+                write!(
+                    f,
+                    "Invalid shape: {}, which should be a tuple of non-negative integers",
+                    value
+                )
+            }
             ParseError::ParseInt(error) => {
                 write!(f, "Invalid integer found in string: {}", error)
             }
+            ParseError::InvalidIntegerLiteral(literal) => {
+                write!(f, "Invalid integer literal: '{}'", literal)
+            }
         }
     }
 }
 
 impl ParseError {
+    pub fn unknown_identifier(identifier: &str) -> Self {
+        ParseError::UnknownIdentifier(identifier.to_string())
+    }
+
+    pub fn invalid_integer_literal(literal: &str) -> Self {
+        ParseError::InvalidIntegerLiteral(literal.to_string())
+    }
+
+    pub fn not_a_dictionary(value: &Value) -> Self {
+        ParseError::NotADictionary(value.to_string())
+    }
+
     pub fn missing_key_value_pairs(key: &str) -> Self {
         ParseError::MissingKeyValuePairs(key.to_string())
     }
@@ -40,7 +107,15 @@ impl ParseError {
         ParseError::MultipleKeyValuePairs(key.to_string())
     }
 
+    pub fn invalid_descr(value: &Value) -> Self {
+        ParseError::InvalidDescr(value.to_string())
+    }
+
     pub fn invalid_bool_found_in_string(invalid_value: &str) -> Self {
         ParseError::InvalidBoolFoundInString(invalid_value.to_string())
     }
+
+    pub fn invalid_shape(value: &Value) -> Self {
+        ParseError::InvalidShape(value.to_string())
+    }
 }
@@ -0,0 +1,400 @@
+//! A tiny recursive-descent parser for the subset of Python literals NumPy emits in NPY headers.
+//!
+//! Supported are dictionaries, lists, tuples, strings, integers, booleans and `None`,
+//! which is what `ast.literal_eval` is used for on the Python side.
+
+use crate::dtype::repr_str;
+use crate::reader::error::ParseError;
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Dict(Vec<(Value, Value)>),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Str(String),
+    /// Wide enough to hold any `usize` dimension as well as negative values,
+    /// so that out-of-range shapes are reported by the caller rather than by the parser.
+    Int(i128),
+    Bool(bool),
+    None,
+}
+
+impl std::fmt::Display for Value {
+    /// Formats the value as Python's `repr` would.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let join = |values: &[Value]| -> String {
+            values
+                .iter()
+                .map(|value: &Value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            Value::Dict(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Value::List(values) => write!(f, "[{}]", join(values)),
+            Value::Tuple(values) if values.len() == 1 => write!(f, "({},)", values[0]),
+            Value::Tuple(values) => write!(f, "({})", join(values)),
+            Value::Str(string) => write!(f, "{}", repr_str(string)),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Bool(true) => write!(f, "True"),
+            Value::Bool(false) => write!(f, "False"),
+            Value::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Maximum nesting of containers, which is far beyond what nested record dtypes need
+/// while keeping the recursion from overflowing the stack.
+const MAX_DEPTH: usize = 32usize;
+
+struct Parser<'a> {
+    buf: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.buf[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Result<char, ParseError> {
+        let character: char = self.peek().ok_or(ParseError::UnexpectedEndOfInput)?;
+        self.pos += character.len_utf8();
+        Ok(character)
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(character) = self.peek() {
+            if !character.is_whitespace() {
+                break;
+            }
+            self.pos += character.len_utf8();
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        match self.peek() {
+            Some(character) => ParseError::UnexpectedCharacter(character, self.pos),
+            None => ParseError::UnexpectedEndOfInput,
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespaces();
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected());
+        }
+        self.pos += expected.len_utf8();
+        Ok(())
+    }
+
+    /// Consumes a separating comma if any, and reports whether the sequence is closed.
+    fn separator(&mut self, closing: char) -> Result<bool, ParseError> {
+        self.skip_whitespaces();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                self.skip_whitespaces();
+                Ok(false)
+            }
+            Some(character) if character == closing => Ok(true),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses a container, keeping track of how deeply containers are nested.
+    fn nested(
+        &mut self,
+        container: fn(&mut Self) -> Result<Value, ParseError>,
+    ) -> Result<Value, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError::TooDeeplyNested(self.pos));
+        }
+        self.depth += 1;
+        let value: Result<Value, ParseError> = container(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespaces();
+        match self.peek() {
+            Some('{') => self.nested(Self::dict),
+            Some('[') => self.nested(Self::list),
+            Some('(') => self.nested(Self::tuple),
+            Some('\'') | Some('"') => self.string(),
+            Some('-') | Some('+') | Some('0'..='9') => self.int(),
+            Some(character) if character.is_alphabetic() => self.keyword(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn dict(&mut self) -> Result<Value, ParseError> {
+        self.expect('{')?;
+        let mut pairs = Vec::<(Value, Value)>::new();
+        self.skip_whitespaces();
+        while self.peek() != Some('}') {
+            let key: Value = self.value()?;
+            self.expect(':')?;
+            let value: Value = self.value()?;
+            pairs.push((key, value));
+            if self.separator('}')? {
+                break;
+            }
+        }
+        self.expect('}')?;
+        Ok(Value::Dict(pairs))
+    }
+
+    fn list(&mut self) -> Result<Value, ParseError> {
+        self.expect('[')?;
+        let mut values = Vec::<Value>::new();
+        self.skip_whitespaces();
+        while self.peek() != Some(']') {
+            values.push(self.value()?);
+            if self.separator(']')? {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(Value::List(values))
+    }
+
+    fn tuple(&mut self) -> Result<Value, ParseError> {
+        self.expect('(')?;
+        let mut values = Vec::<Value>::new();
+        let mut has_comma = false;
+        self.skip_whitespaces();
+        while self.peek() != Some(')') {
+            values.push(self.value()?);
+            if self.separator(')')? {
+                break;
+            }
+            has_comma = true;
+        }
+        self.expect(')')?;
+        // a parenthesised value without any comma is not a tuple but the value itself
+        if values.len() == 1 && !has_comma {
+            return Ok(values.remove(0));
+        }
+        Ok(Value::Tuple(values))
+    }
+
+    fn string(&mut self) -> Result<Value, ParseError> {
+        let start: usize = self.pos;
+        let quote: char = self.bump()?;
+        let mut string = String::new();
+        loop {
+            let character: char = self
+                .bump()
+                .map_err(|_| ParseError::UnterminatedString(start))?;
+            match character {
+                c if c == quote => break,
+                '\\' => string.push(self.escape_sequence()?),
+                '\n' => return Err(ParseError::UnterminatedString(start)),
+                c => string.push(c),
+            }
+        }
+        Ok(Value::Str(string))
+    }
+
+    fn escape_sequence(&mut self) -> Result<char, ParseError> {
+        let start: usize = self.pos - 1;
+        let hex = |parser: &mut Self, ndigits: usize| -> Result<char, ParseError> {
+            let digits: String = (0..ndigits)
+                .map(|_| parser.bump())
+                .collect::<Result<String, ParseError>>()?;
+            // from_str_radix alone accepts a leading sign
+            if !digits.chars().all(|c: char| c.is_ascii_hexdigit()) {
+                return Err(ParseError::InvalidEscapeSequence(start));
+            }
+            u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or(ParseError::InvalidEscapeSequence(start))
+        };
+        let character: char = match self.bump()? {
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'x' => hex(self, 2usize)?,
+            'u' => hex(self, 4usize)?,
+            'U' => hex(self, 8usize)?,
+            _ => return Err(ParseError::InvalidEscapeSequence(start)),
+        };
+        Ok(character)
+    }
+
+    /// Parses a decimal integer, following Python's rules:
+    /// underscores are allowed only between digits,
+    /// and leading zeros only when all digits are zero.
+    fn int(&mut self) -> Result<Value, ParseError> {
+        let start: usize = self.pos;
+        if matches!(self.peek(), Some('-') | Some('+')) {
+            self.pos += 1;
+        }
+        let body: usize = self.pos;
+        while matches!(self.peek(), Some('0'..='9') | Some('_')) {
+            self.pos += 1;
+        }
+        let literal: &str = &self.buf[body..self.pos];
+        let misplaced_underscore: bool =
+            literal.starts_with('_') || literal.ends_with('_') || literal.contains("__");
+        let leading_zeros: bool =
+            literal.starts_with('0') && literal.chars().any(|c: char| matches!(c, '1'..='9'));
+        if misplaced_underscore || leading_zeros {
+            return Err(ParseError::invalid_integer_literal(
+                &self.buf[start..self.pos],
+            ));
+        }
+        let digits: String = self.buf[start..self.pos].replace('_', "");
+        let value: i128 = digits.parse::<i128>().map_err(ParseError::ParseInt)?;
+        Ok(Value::Int(value))
+    }
+
+    fn keyword(&mut self) -> Result<Value, ParseError> {
+        let start: usize = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '_') {
+            self.pos += self.peek().map_or(0usize, char::len_utf8);
+        }
+        match &self.buf[start..self.pos] {
+            "True" => Ok(Value::Bool(true)),
+            "False" => Ok(Value::Bool(false)),
+            "None" => Ok(Value::None),
+            word => Err(ParseError::unknown_identifier(word)),
+        }
+    }
+}
+
+/// Parses a single Python literal, allowing surrounding white spaces only.
+pub fn parse(buf: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        buf,
+        pos: 0usize,
+        depth: 0usize,
+    };
+    let value: Value = parser.value()?;
+    parser.skip_whitespaces();
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Value};
+    use crate::reader::error::ParseError;
+
+    #[test]
+    fn parse_scalars() {
+        assert_eq!(parse("'<f8'"), Ok(Value::Str("<f8".to_string())));
+        assert_eq!(parse(r#""<f8""#), Ok(Value::Str("<f8".to_string())));
+        assert_eq!(parse(r"'\'\\\x41あ'"), Ok(Value::Str("'\\Aあ".to_string())));
+        assert_eq!(parse(" 42 "), Ok(Value::Int(42)));
+        assert_eq!(parse("-1"), Ok(Value::Int(-1)));
+        assert_eq!(parse("1_000"), Ok(Value::Int(1000)));
+        assert_eq!(parse("0_0"), Ok(Value::Int(0)));
+        assert_eq!(parse("-00"), Ok(Value::Int(0)));
+        assert_eq!(
+            parse("18446744073709551615"),
+            Ok(Value::Int(u64::MAX as i128))
+        );
+        assert_eq!(parse("True"), Ok(Value::Bool(true)));
+        assert_eq!(parse("False"), Ok(Value::Bool(false)));
+        assert_eq!(parse("None"), Ok(Value::None));
+    }
+
+    #[test]
+    fn parse_containers() {
+        assert_eq!(parse("()"), Ok(Value::Tuple(vec![])));
+        assert_eq!(parse("(3)"), Ok(Value::Int(3)));
+        assert_eq!(parse("(3,)"), Ok(Value::Tuple(vec![Value::Int(3)])));
+        assert_eq!(
+            parse("(3, 5, )"),
+            Ok(Value::Tuple(vec![Value::Int(3), Value::Int(5)]))
+        );
+        assert_eq!(
+            parse("[('shape', '<f8', (3,))]"),
+            Ok(Value::List(vec![Value::Tuple(vec![
+                Value::Str("shape".to_string()),
+                Value::Str("<f8".to_string()),
+                Value::Tuple(vec![Value::Int(3)]),
+            ])]))
+        );
+        assert_eq!(
+            parse("{'a': [], 'b': None, }\n"),
+            Ok(Value::Dict(vec![
+                (Value::Str("a".to_string()), Value::List(vec![])),
+                (Value::Str("b".to_string()), Value::None),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_corner() {
+        assert_eq!(parse(""), Err(ParseError::UnexpectedEndOfInput));
+        assert_eq!(
+            parse("(1,,2)"),
+            Err(ParseError::UnexpectedCharacter(',', 3))
+        );
+        assert_eq!(parse("(1 2)"), Err(ParseError::UnexpectedCharacter('2', 3)));
+        assert_eq!(
+            parse("{'a' 1}"),
+            Err(ParseError::UnexpectedCharacter('1', 5))
+        );
+        assert_eq!(parse("'<f8"), Err(ParseError::UnterminatedString(0)));
+        assert_eq!(parse(r#"'<f8""#), Err(ParseError::UnterminatedString(0)));
+        assert_eq!(parse(r"'\q'"), Err(ParseError::InvalidEscapeSequence(1)));
+        assert_eq!(parse("true"), Err(ParseError::unknown_identifier("true")));
+        assert_eq!(parse("1 1"), Err(ParseError::UnexpectedCharacter('1', 2)));
+        assert!(matches!(parse("-"), Err(ParseError::ParseInt(_))));
+        for literal in ["1_", "1__2", "-_1", "007", "0_1"] {
+            assert_eq!(
+                parse(literal),
+                Err(ParseError::invalid_integer_literal(literal))
+            );
+        }
+        assert_eq!(parse(r"'\x+1'"), Err(ParseError::InvalidEscapeSequence(1)));
+        assert_eq!(parse(r"'\x4'"), Err(ParseError::InvalidEscapeSequence(1)));
+    }
+
+    #[test]
+    fn parse_deeply_nested() {
+        let nested = |depth: usize| -> String { "[".repeat(depth) + &"]".repeat(depth) };
+        assert!(parse(&nested(32usize)).is_ok());
+        assert_eq!(
+            parse(&nested(33usize)),
+            Err(ParseError::TooDeeplyNested(32))
+        );
+        // the limit is reached long before the stack overflows
+        assert_eq!(
+            parse(&nested(200000usize)),
+            Err(ParseError::TooDeeplyNested(32))
+        );
+    }
+
+    #[test]
+    fn repr() {
+        for literal in [
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (3,)}",
+            "[('x', '<f8'), ('v', '<f4', (2, 3))]",
+            r#""it's""#,
+            r"'\\\n'",
+            "None",
+        ] {
+            assert_eq!(parse(literal).unwrap().to_string(), literal);
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Reading and writing parts of arrays without touching the rest of the payload,
+//! either rectangular ones or arbitrary rows.
+
+use crate::element::{self, Element};
+use crate::error::{LayoutError, SelectionError};
+use crate::{HeaderInfo, Layout};
+
+/// Gaps up to this size between the selected bytes are read and discarded,
+/// rather than being skipped by an extra seek.
+const MAX_GAP: u64 = 4096u64;
+/// Upper bound of the bytes read at once when gaps are bridged.
+const MAX_SPAN: u64 = 1u64 << 20;
+
+/// An N-dimensional selection, picking `count[n]` indices `start[n]`, `start[n] + step[n]`, ...
+/// along each axis `n`, as `a[start:start + count * step:step]` does in NumPy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hyperslab {
+    pub start: Vec<usize>,
+    pub count: Vec<usize>,
+    pub step: Vec<usize>,
+}
+
+impl Hyperslab {
+    /// A contiguous block, i.e. with unit steps.
+    pub fn block(start: &[usize], count: &[usize]) -> Self {
+        Hyperslab {
+            start: start.to_vec(),
+            count: count.to_vec(),
+            step: vec![1usize; start.len()],
+        }
+    }
+
+    /// Checks that the selection has the same dimension as `shape` and fits in it.
+    fn validate(&self, shape: &[usize]) -> Result<(), SelectionError> {
+        let ndim: usize = shape.len();
+        let valid: bool = self.start.len() == ndim
+            && self.count.len() == ndim
+            && self.step.len() == ndim
+            && (0usize..ndim).all(|n: usize| {
+                if self.step[n] == 0usize {
+                    return false;
+                }
+                if self.count[n] == 0usize {
+                    return self.start[n] <= shape[n];
+                }
+                (self.count[n] - 1usize)
+                    .checked_mul(self.step[n])
+                    .and_then(|last: usize| last.checked_add(self.start[n]))
+                    .is_some_and(|last: usize| last < shape[n])
+            });
+        if !valid {
+            return Err(SelectionError::InvalidHyperslab(
+                self.clone(),
+                shape.to_vec(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Number of elements in a selection of the given counts, checking overflow.
+fn element_count(count: &[usize]) -> Option<usize> {
+    count
+        .iter()
+        .try_fold(1usize, |total: usize, &n: &usize| total.checked_mul(n))
+}
+
+/// Layout of the payload.
+///
+/// This is checked separately from the payload size,
+/// as the strides may overflow even when an empty axis makes the payload empty.
+fn layout_of(header_info: &HeaderInfo) -> Result<Layout, LayoutError> {
+    Layout::from_header(&header_info.header)
+}
+
+/// Contiguous byte ranges `(offset, size)` of a hyperslab relative to the payload,
+/// in increasing order of offset.
+pub(crate) struct Runs {
+    /// Byte strides, start, count and step of each axis in the storage order.
+    strides: Vec<u64>,
+    start: Vec<usize>,
+    count: Vec<usize>,
+    step: Vec<usize>,
+    /// Number of outer axes iterated one index at a time,
+    /// while the remaining (fastest) axis is read at once when its step is one.
+    outer_ndim: usize,
+    segment_size: usize,
+    index: Vec<usize>,
+    done: bool,
+    pending: Option<(u64, usize)>,
+}
+
+impl Runs {
+    pub(crate) fn new(header_info: &HeaderInfo, slab: &Hyperslab) -> Result<Self, LayoutError> {
+        let layout: Layout = layout_of(header_info)?;
+        let axes: Vec<usize> = layout.storage_axes();
+        let byte_strides: Vec<usize> = layout.byte_strides();
+        let ordered =
+            |values: &[usize]| -> Vec<usize> { axes.iter().map(|&n: &usize| values[n]).collect() };
+        let strides: Vec<u64> = ordered(&byte_strides)
+            .into_iter()
+            .map(|stride: usize| stride as u64)
+            .collect();
+        let start: Vec<usize> = ordered(&slab.start);
+        let count: Vec<usize> = ordered(&slab.count);
+        let step: Vec<usize> = ordered(&slab.step);
+        let ndim: usize = axes.len();
+        let itemsize: usize = layout.itemsize();
+        let (outer_ndim, segment_size): (usize, usize) = match step.last() {
+            Some(1usize) => (ndim - 1usize, count[ndim - 1usize] * itemsize),
+            _ => (ndim, itemsize),
+        };
+        Ok(Runs {
+            strides,
+            done: count.contains(&0usize),
+            start,
+            count,
+            step,
+            outer_ndim,
+            segment_size,
+            index: vec![0usize; outer_ndim],
+            pending: None,
+        })
+    }
+
+    fn next_segment(&mut self) -> Option<(u64, usize)> {
+        if self.done {
+            return None;
+        }
+        let offset: u64 = (0usize..self.strides.len())
+            .map(|n: usize| {
+                let index: usize = self.index.get(n).copied().unwrap_or(0usize);
+                (self.start[n] + index * self.step[n]) as u64 * self.strides[n]
+            })
+            .sum();
+        self.done = true;
+        for n in (0usize..self.outer_ndim).rev() {
+            self.index[n] += 1usize;
+            if self.index[n] < self.count[n] {
+                self.done = false;
+                break;
+            }
+            self.index[n] = 0usize;
+        }
+        Some((offset, self.segment_size))
+    }
+}
+
+impl Iterator for Runs {
+    type Item = (u64, usize);
+
+    fn next(&mut self) -> Option<(u64, usize)> {
+        loop {
+            let segment: Option<(u64, usize)> = self.next_segment();
+            match (segment, self.pending) {
+                (None, _) => return self.pending.take(),
+                (Some((offset, size)), Some((pending_offset, pending_size)))
+                    if pending_offset + pending_size as u64 == offset =>
+                {
+                    self.pending = Some((pending_offset, pending_size + size));
+                }
+                (Some(segment), pending) => {
+                    self.pending = Some(segment);
+                    if pending.is_some() {
+                        return pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reads the bytes of the runs one after another, bridging small gaps between them.
+///
+/// Spans without gaps are read directly into `buf`,
+/// while those with gaps go through a temporary buffer of at most `MAX_SPAN` bytes.
+pub(crate) fn read_runs<R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+    data_offset: u64,
+    runs: impl Iterator<Item = (u64, usize)>,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    let mut runs = runs.peekable();
+    let mut span = Vec::<u8>::new();
+    let mut pieces = Vec::<(u64, usize)>::new();
+    while let Some((start, size)) = runs.next() {
+        pieces.clear();
+        pieces.push((start, size));
+        let mut end: u64 = start + size as u64;
+        let mut has_gaps = false;
+        while let Some(&(offset, size)) = runs.peek() {
+            if end + MAX_GAP < offset || start + MAX_SPAN < offset + size as u64 {
+                break;
+            }
+            pieces.push((offset, size));
+            has_gaps |= end != offset;
+            end = offset + size as u64;
+            runs.next();
+        }
+        f.seek(std::io::SeekFrom::Start(data_offset + start))?;
+        if !has_gaps {
+            let len: usize = buf.len();
+            buf.resize(len + (end - start) as usize, 0u8);
+            f.read_exact(&mut buf[len..])?;
+            continue;
+        }
+        span.resize((end - start) as usize, 0u8);
+        f.read_exact(&mut span)?;
+        for &(offset, size) in pieces.iter() {
+            let offset: usize = (offset - start) as usize;
+            buf.extend_from_slice(&span[offset..offset + size]);
+        }
+    }
+    Ok(())
+}
+
+/// Writes `buf` to the runs one after another, writing adjacent runs at once
+/// and seeking only when a run does not follow the previous one.
+///
+/// `position` is the position of the stream relative to the payload if known,
+/// which is kept up to date so that successive calls avoid needless seeks as well.
+#[cfg(feature = "writer")]
+pub(crate) fn write_runs<W: std::io::Write + std::io::Seek>(
+    f: &mut W,
+    data_offset: u64,
+    runs: impl Iterator<Item = (u64, usize)>,
+    buf: &[u8],
+    position: &mut Option<u64>,
+) -> std::io::Result<()> {
+    let mut runs = runs.peekable();
+    let mut written: usize = 0usize;
+    while let Some((start, mut size)) = runs.next() {
+        while let Some(&(offset, next)) = runs.peek() {
+            if start + size as u64 != offset {
+                break;
+            }
+            size += next;
+            runs.next();
+        }
+        if *position != Some(start) {
+            f.seek(std::io::SeekFrom::Start(data_offset + start))?;
+        }
+        // unknown until the write succeeds
+        *position = None;
+        f.write_all(&buf[written..written + size])?;
+        written += size;
+        *position = Some(start + size as u64);
+    }
+    Ok(())
+}
+
+/// Reads the selected elements, seeking over the others.
+///
+/// The NPY data is expected to start at the beginning of the stream,
+/// and `header_info` is the one read from it, e.g. by [`crate::read_header_info`].
+/// The elements are returned in the order of the file, i.e. following `fortran_order`,
+/// as an array of shape `slab.count`.
+pub fn read_hyperslab<T: Element, R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+    header_info: &HeaderInfo,
+    slab: &Hyperslab,
+) -> Result<Vec<T>, SelectionError> {
+    let descr = &header_info.header.descr;
+    let byte_order = element::byte_order_of::<T>(descr)
+        .ok_or_else(|| SelectionError::DTypeMismatch(descr.clone(), T::dtype()))?;
+    slab.validate(&header_info.header.shape)?;
+    // bounded by the payload size, as the selection fits in the shape
+    let count: usize = element_count(&slab.count).unwrap_or(0usize);
+    let mut buf = Vec::<u8>::with_capacity(count * header_info.itemsize);
+    let runs = Runs::new(header_info, slab)?;
+    read_runs(f, header_info.data_offset as u64, runs, &mut buf)?;
+    Ok(element::decode::<T>(&buf, byte_order))
+}
+
+/// Writes `data` to the selected elements of an existing file, leaving the others untouched,
+/// so that, e.g., each process can fill its own subdomain of a preallocated array.
+///
+/// `data` is an array of shape `slab.count` in the order of the file,
+/// and is stored in the byte order of the file.
+/// As with [`read_hyperslab`], the NPY data is expected to start at the beginning of the stream.
+#[cfg(feature = "writer")]
+pub fn write_hyperslab<T: Element, W: std::io::Write + std::io::Seek>(
+    f: &mut W,
+    header_info: &HeaderInfo,
+    slab: &Hyperslab,
+    data: &[T],
+) -> Result<(), SelectionError> {
+    let descr = &header_info.header.descr;
+    let byte_order = element::byte_order_of::<T>(descr)
+        .ok_or_else(|| SelectionError::DTypeMismatch(descr.clone(), T::dtype()))?;
+    slab.validate(&header_info.header.shape)?;
+    if element_count(&slab.count) != Some(data.len()) {
+        return Err(SelectionError::ShapeMismatch(
+            slab.count.clone(),
+            data.len(),
+        ));
+    }
+    let mut buf = Vec::<u8>::new();
+    element::encode(data, byte_order, &mut buf);
+    let runs = Runs::new(header_info, slab)?;
+    write_runs(f, header_info.data_offset as u64, runs, &buf, &mut None)?;
+    f.flush()?;
+    Ok(())
+}
+
+/// Number of indices of the outermost axis and the size of one of them in bytes.
+///
+/// A zero-dimensional array is treated as a single row of one element.
+fn rows_of(header_info: &HeaderInfo) -> Result<(usize, usize), SelectionError> {
+    let layout: Layout = layout_of(header_info)?;
+    let (rows, row_size): (usize, usize) = match layout.outermost_axis() {
+        Some(n) => (layout.shape()[n], layout.byte_strides()[n]),
+        None => (1usize, layout.itemsize()),
+    };
+    Ok((rows, row_size))
+}
+
+/// Shape of the given number of rows.
+fn shape_of_rows(header_info: &HeaderInfo, rows: usize) -> Result<Vec<usize>, SelectionError> {
+    let mut shape: Vec<usize> = header_info.header.shape.clone();
+    if let Some(n) = layout_of(header_info)?.outermost_axis() {
+        shape[n] = rows;
+    }
+    Ok(shape)
+}
+
+/// Reads the given indices of the outermost axis, which may be unsorted or repeated,
+/// as `a[rows]` in C order and `a[..., rows]` in Fortran order do in NumPy.
+///
+/// The indices are sorted and merged so that the file is read forward once.
+/// The rows are returned in the requested order, and the NPY data is expected
+/// to start at the beginning of the stream as with [`read_hyperslab`].
+pub fn gather_rows<T: Element, R: std::io::Read + std::io::Seek>(
+    f: &mut R,
+    header_info: &HeaderInfo,
+    rows: &[usize],
+) -> Result<Vec<T>, SelectionError> {
+    let descr = &header_info.header.descr;
+    let byte_order = element::byte_order_of::<T>(descr)
+        .ok_or_else(|| SelectionError::DTypeMismatch(descr.clone(), T::dtype()))?;
+    let (nrows, row_size): (usize, usize) = rows_of(header_info)?;
+    let mut sorted: Vec<usize> = rows.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if let Some(&row) = sorted.last().filter(|&&row: &&usize| nrows <= row) {
+        return Err(SelectionError::RowOutOfBounds(row, nrows));
+    }
+    let row_len: usize = row_size / header_info.itemsize.max(1usize);
+    let overflow = || -> SelectionError {
+        match shape_of_rows(header_info, rows.len()) {
+            Ok(shape) => SelectionError::Layout(LayoutError::SizeOverflow(shape)),
+            Err(error) => error,
+        }
+    };
+    let buf_size: usize = sorted.len().checked_mul(row_size).ok_or_else(overflow)?;
+    let gathered_len: usize = rows.len().checked_mul(row_len).ok_or_else(overflow)?;
+    let runs = sorted
+        .iter()
+        .map(|&row: &usize| (row as u64 * row_size as u64, row_size));
+    let mut buf = Vec::<u8>::with_capacity(buf_size);
+    read_runs(f, header_info.data_offset as u64, runs, &mut buf)?;
+    let data: Vec<T> = element::decode::<T>(&buf, byte_order);
+    let mut gathered = Vec::<T>::with_capacity(gathered_len);
+    for row in rows {
+        // always found, as the sorted indices are made from the requested ones
+        let position: usize = sorted.binary_search(row).unwrap_or(0usize);
+        gathered.extend_from_slice(&data[position * row_len..(position + 1usize) * row_len]);
+    }
+    Ok(gathered)
+}
+
+/// Writes `data`, holding one row for each index in `rows`, to the given indices of the outermost axis,
+/// as `a[rows] = data` does in NumPy, i.e. the last one wins for repeated indices.
+///
+/// Consecutive indices are written at once, in the byte order of the file.
+#[cfg(feature = "writer")]
+pub fn scatter_rows<T: Element, W: std::io::Write + std::io::Seek>(
+    f: &mut W,
+    header_info: &HeaderInfo,
+    rows: &[usize],
+    data: &[T],
+) -> Result<(), SelectionError> {
+    let descr = &header_info.header.descr;
+    let byte_order = element::byte_order_of::<T>(descr)
+        .ok_or_else(|| SelectionError::DTypeMismatch(descr.clone(), T::dtype()))?;
+    let (nrows, row_size): (usize, usize) = rows_of(header_info)?;
+    let row_len: usize = row_size / header_info.itemsize.max(1usize);
+    if rows.len().checked_mul(row_len) != Some(data.len()) {
+        return Err(SelectionError::ShapeMismatch(
+            shape_of_rows(header_info, rows.len())?,
+            data.len(),
+        ));
+    }
+    if let Some(&row) = rows.iter().find(|&&row: &&usize| nrows <= row) {
+        return Err(SelectionError::RowOutOfBounds(row, nrows));
+    }
+    let mut buf = Vec::<u8>::new();
+    element::encode(data, byte_order, &mut buf);
+    // sorted by the index, keeping the last one of the repeated indices
+    let mut order: Vec<(usize, usize)> = rows
+        .iter()
+        .enumerate()
+        .map(|(position, &row): (usize, &usize)| (row, position))
+        .collect();
+    order.sort_by_key(|&(row, _): &(usize, usize)| row);
+    order.reverse();
+    order.dedup_by_key(|(row, _): &mut (usize, usize)| *row);
+    order.reverse();
+    let mut block = Vec::<u8>::new();
+    let mut n: usize = 0usize;
+    while n < order.len() {
+        let first: usize = order[n].0;
+        let mut next: usize = first;
+        block.clear();
+        while n < order.len() && order[n].0 == next {
+            let position: usize = order[n].1;
+            block.extend_from_slice(&buf[position * row_size..(position + 1usize) * row_size]);
+            n += 1usize;
+            next += 1usize;
+        }
+        f.seek(std::io::SeekFrom::Start(
+            header_info.data_offset as u64 + first as u64 * row_size as u64,
+        ))?;
+        f.write_all(&block)?;
+    }
+    f.flush()?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "writer"))]
+mod tests {
+    use super::{gather_rows, read_hyperslab, scatter_rows, write_hyperslab, Hyperslab};
+    use crate::error::{LayoutError, SelectionError};
+    use std::io::Cursor;
+
+    fn array(shape: &[usize], fortran_order: bool) -> (Cursor<Vec<u8>>, crate::HeaderInfo) {
+        let count: usize = shape.iter().product();
+        let data: Vec<u32> = (0u32..count as u32).collect();
+        let mut buf = Vec::<u8>::new();
+        crate::write_array(&mut buf, shape, fortran_order, &data).unwrap();
+        let mut f = Cursor::new(buf);
+        let header_info = crate::read_header_info(&mut f).unwrap();
+        (f, header_info)
+    }
+
+    #[test]
+    fn read_planes() {
+        // value = 20 i + 5 j + k in C order
+        let (mut f, info) = array(&[3usize, 4usize, 5usize], false);
+        let plane = Hyperslab::block(&[0, 2, 0], &[3, 1, 5]);
+        let expected: Vec<u32> = (0u32..3u32)
+            .flat_map(|i: u32| (0u32..5u32).map(move |k: u32| 20 * i + 10 + k))
+            .collect();
+        assert_eq!(
+            read_hyperslab::<u32, _>(&mut f, &info, &plane).unwrap(),
+            expected
+        );
+        let strided = Hyperslab {
+            start: vec![1, 0, 1],
+            count: vec![2, 2, 2],
+            step: vec![1, 3, 2],
+        };
+        assert_eq!(
+            read_hyperslab::<u32, _>(&mut f, &info, &strided).unwrap(),
+            vec![21, 23, 36, 38, 41, 43, 56, 58]
+        );
+        // value = i + 3 j + 12 k in Fortran order
+        let (mut f, info) = array(&[3usize, 4usize, 5usize], true);
+        assert_eq!(
+            read_hyperslab::<u32, _>(&mut f, &info, &strided).unwrap(),
+            vec![13, 14, 22, 23, 37, 38, 46, 47]
+        );
+    }
+
+    #[test]
+    fn write_blocks() {
+        for fortran_order in [false, true] {
+            let (mut f, info) = array(&[4usize, 6usize], fortran_order);
+            let whole = Hyperslab::block(&[0, 0], &[4, 6]);
+            let original: Vec<u32> = read_hyperslab(&mut f, &info, &whole).unwrap();
+            let block = Hyperslab::block(&[1, 2], &[2, 3]);
+            write_hyperslab(&mut f, &info, &block, &[100u32; 6]).unwrap();
+            assert_eq!(
+                read_hyperslab::<u32, _>(&mut f, &info, &block).unwrap(),
+                vec![100u32; 6]
+            );
+            let updated: Vec<u32> = read_hyperslab(&mut f, &info, &whole).unwrap();
+            let changed: usize = original
+                .iter()
+                .zip(updated.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+            assert_eq!(changed, 6usize);
+        }
+    }
+
+    #[test]
+    fn invalid_selections() {
+        let (mut f, info) = array(&[4usize, 6usize], false);
+        for slab in [
+            Hyperslab::block(&[0], &[4]),
+            Hyperslab::block(&[3, 0], &[2, 6]),
+            Hyperslab {
+                start: vec![0, 0],
+                count: vec![1, 4],
+                step: vec![0, 2],
+            },
+        ] {
+            assert!(matches!(
+                read_hyperslab::<u32, _>(&mut f, &info, &slab),
+                Err(SelectionError::InvalidHyperslab(..))
+            ));
+        }
+        let empty = Hyperslab::block(&[4, 0], &[0, 6]);
+        assert!(read_hyperslab::<u32, _>(&mut f, &info, &empty)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            write_hyperslab(
+                &mut f,
+                &info,
+                &Hyperslab::block(&[0, 0], &[2, 2]),
+                &[0u32; 3]
+            ),
+            Err(SelectionError::ShapeMismatch(..))
+        ));
+        assert!(matches!(
+            read_hyperslab::<i32, _>(&mut f, &info, &empty),
+            Err(SelectionError::DTypeMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn gather_and_scatter() {
+        for fortran_order in [false, true] {
+            // rows of two elements, i.e. (2 r, 2 r + 1)
+            let shape: [usize; 2] = if fortran_order { [2, 10] } else { [10, 2] };
+            let (mut f, info) = array(&shape, fortran_order);
+            assert_eq!(
+                gather_rows::<u32, _>(&mut f, &info, &[7, 2, 7, 3]).unwrap(),
+                vec![14, 15, 4, 5, 14, 15, 6, 7]
+            );
+            assert!(gather_rows::<u32, _>(&mut f, &info, &[])
+                .unwrap()
+                .is_empty());
+            scatter_rows(
+                &mut f,
+                &info,
+                &[5, 4, 9, 4],
+                &[50u32, 51, 40, 41, 90, 91, 42, 43],
+            )
+            .unwrap();
+            assert_eq!(
+                gather_rows::<u32, _>(&mut f, &info, &[3, 4, 5, 6, 9]).unwrap(),
+                vec![6, 7, 42, 43, 50, 51, 12, 13, 90, 91]
+            );
+            assert!(matches!(
+                gather_rows::<u32, _>(&mut f, &info, &[1, 10]),
+                Err(SelectionError::RowOutOfBounds(10usize, 10usize))
+            ));
+            assert!(matches!(
+                scatter_rows(&mut f, &info, &[1], &[0u32; 3]),
+                Err(SelectionError::ShapeMismatch(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn overflowing_layout() {
+        // the payload is empty, while the strides overflow
+        let header = crate::Header {
+            descr: crate::Descr::Simple("<f8".parse::<crate::DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![0usize, usize::MAX / 2usize, 4usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        crate::write_header(&mut buf, &header, &crate::WriterOptions::default()).unwrap();
+        let mut f = Cursor::new(buf);
+        let info = crate::read_header_info(&mut f).unwrap();
+        assert_eq!(info.payload_size, 0usize);
+        let empty = Hyperslab::block(&[0, 0, 0], &[0, 1, 1]);
+        assert!(matches!(
+            read_hyperslab::<f64, _>(&mut f, &info, &empty),
+            Err(SelectionError::Layout(LayoutError::SizeOverflow(_)))
+        ));
+        assert!(matches!(
+            gather_rows::<f64, _>(&mut f, &info, &[]),
+            Err(SelectionError::Layout(LayoutError::SizeOverflow(_)))
+        ));
+        assert!(matches!(
+            scatter_rows::<f64, _>(&mut f, &info, &[], &[]),
+            Err(SelectionError::Layout(LayoutError::SizeOverflow(_)))
+        ));
+    }
+
+    #[test]
+    fn overflowing_gather() {
+        // a valid layout, while repeating the rows overflows
+        let header = crate::Header {
+            descr: crate::Descr::Simple("|u1".parse::<crate::DType>().unwrap()),
+            fortran_order: false,
+            shape: vec![2usize, usize::MAX / 4usize],
+        };
+        let mut buf = Vec::<u8>::new();
+        crate::write_header(&mut buf, &header, &crate::WriterOptions::default()).unwrap();
+        let mut f = Cursor::new(buf);
+        let info = crate::read_header_info(&mut f).unwrap();
+        assert!(matches!(
+            gather_rows::<u8, _>(&mut f, &info, &[0, 0, 0, 0, 0]),
+            Err(SelectionError::Layout(LayoutError::SizeOverflow(shape)))
+                if shape == vec![5usize, usize::MAX / 4usize]
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+//! Writing arrays whose outermost axis is not known in advance,
+//! similar to the `npy-append-array` Python package.
+
+use crate::dtype::{ByteOrder, Descr};
+use crate::element::{self, Element};
+use crate::error::StreamWriterError;
+use crate::{writer, Header, WriterOptions};
+
+/// Writes rows of an array one after another,
+/// and rewrites the length of the outermost axis in the header when finished.
+///
+/// The outermost axis is the first one in C order and the last one in Fortran order.
+/// The provisional header reserves `options.growth_axis_max_digits` spare spaces for the shape.
+/// With the default options, this is what `numpy.save` does,
+/// so that the final header has the same size and the same contents as the one `numpy.save` writes.
+///
+/// Dropping the writer without [`StreamWriter::finish`] finishes it as well.
+/// It panics when the elements do not fill a whole number of rows,
+/// while other errors, e.g. I/O ones, are ignored and the header may be left provisional.
+/// Call [`StreamWriter::finish`] to handle them.
+pub struct StreamWriter<W: std::io::Write + std::io::Seek, T: Element> {
+    inner: Option<W>,
+    header: Header,
+    /// Position of the magic string.
+    start: u64,
+    major_version: u8,
+    header_len: usize,
+    /// Number of elements in one index of the outermost axis.
+    row_len: usize,
+    count: usize,
+    byte_order: ByteOrder,
+    buf: Vec<u8>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<W: std::io::Write + std::io::Seek, T: Element> StreamWriter<W, T> {
+    /// Writes a provisional header with no rows, where `row_shape` is the shape without the outermost axis.
+    pub fn new(
+        mut inner: W,
+        row_shape: &[usize],
+        fortran_order: bool,
+        options: &WriterOptions,
+    ) -> Result<Self, StreamWriterError> {
+        let mut shape: Vec<usize> = row_shape.to_vec();
+        if fortran_order {
+            shape.push(0usize);
+        } else {
+            shape.insert(0usize, 0usize);
+        }
+        let row_len: usize = row_shape
+            .iter()
+            .try_fold(1usize, |count: usize, &n: &usize| count.checked_mul(n))
+            .ok_or_else(|| StreamWriterError::SizeOverflow(shape.clone()))?;
+        let dtype = T::dtype();
+        let byte_order: ByteOrder = dtype.byte_order;
+        let header = Header {
+            descr: Descr::Simple(dtype),
+            fortran_order,
+            shape,
+        };
+        let buf: Vec<u8> = writer::prepare_header(&header, options)?;
+        let major_version: u8 = buf[crate::consts::MAGIC_STRING.len()];
+        let header_len: usize = buf.len() - writer::prefix_size(major_version);
+        let start: u64 = inner.stream_position()?;
+        inner.write_all(&buf)?;
+        Ok(StreamWriter {
+            inner: Some(inner),
+            header,
+            start,
+            major_version,
+            header_len,
+            row_len,
+            count: 0usize,
+            byte_order,
+            buf: Vec::new(),
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Appends elements in the order stored in the file,
+    /// which do not have to be a whole number of rows as long as they are when finished.
+    pub fn write(&mut self, data: &[T]) -> Result<(), StreamWriterError> {
+        let count: usize = self
+            .count
+            .checked_add(data.len())
+            .ok_or_else(|| StreamWriterError::SizeOverflow(self.header.shape.clone()))?;
+        self.buf.clear();
+        element::encode(data, self.byte_order, &mut self.buf);
+        self.inner
+            .as_mut()
+            .expect("inner is taken only when finished")
+            .write_all(&self.buf)?;
+        self.count = count;
+        Ok(())
+    }
+
+    /// Number of complete rows written so far.
+    pub fn rows(&self) -> usize {
+        self.count.checked_div(self.row_len).unwrap_or(0usize)
+    }
+
+    /// Rewrites the shape in the header and returns the sink, positioned at the end of the payload.
+    ///
+    /// When the rows have no elements, the outermost axis cannot be counted and is left zero.
+    /// When the new shape does not fit in the reserved spaces,
+    /// [`WriteHeaderError::InsufficientSpace`](crate::error::WriteHeaderError::InsufficientSpace) is returned.
+    pub fn finish(mut self) -> Result<W, StreamWriterError> {
+        self.finalize()
+    }
+
+    fn finalize(&mut self) -> Result<W, StreamWriterError> {
+        let mut inner: W = self
+            .inner
+            .take()
+            .expect("inner is taken only when finished");
+        if self.row_len != 0usize && !self.count.is_multiple_of(self.row_len) {
+            return Err(StreamWriterError::IncompleteRow(self.count, self.row_len));
+        }
+        let rows: usize = self.rows();
+        let ndim: usize = self.header.shape.len();
+        if self.header.fortran_order {
+            self.header.shape[ndim - 1] = rows;
+        } else {
+            self.header.shape[0] = rows;
+        }
+        let buf: Vec<u8> =
+            writer::prepare_header_in_place(&self.header, self.major_version, self.header_len)?;
+        let end: u64 = inner.stream_position()?;
+        inner.seek(std::io::SeekFrom::Start(self.start))?;
+        inner.write_all(&buf)?;
+        inner.seek(std::io::SeekFrom::Start(end))?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: std::io::Write + std::io::Seek, T: Element> Drop for StreamWriter<W, T> {
+    fn drop(&mut self) {
+        if self.inner.is_none() || std::thread::panicking() {
+            return;
+        }
+        // partial rows are a logic error of the caller, which should not pass silently,
+        // whereas I/O errors can only be handled through finish
+        if let Err(error @ StreamWriterError::IncompleteRow(..)) = self.finalize() {
+            panic!("Failed to finish the stream: {}", error);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "reader"))]
+mod tests {
+    use super::StreamWriter;
+    use crate::error::StreamWriterError;
+    use crate::WriterOptions;
+    use std::io::Cursor;
+
+    #[test]
+    fn same_as_write_array() {
+        for fortran_order in [false, true] {
+            let data: Vec<f64> = (0..24).map(|x: i32| x as f64).collect();
+            let mut stream = StreamWriter::<_, f64>::new(
+                Cursor::new(Vec::new()),
+                &[3usize, 2usize],
+                fortran_order,
+                &WriterOptions::default(),
+            )
+            .unwrap();
+            stream.write(&data[..5]).unwrap();
+            stream.write(&data[5..]).unwrap();
+            assert_eq!(stream.rows(), 4usize);
+            let buf: Vec<u8> = stream.finish().unwrap().into_inner();
+            let shape: Vec<usize> = if fortran_order {
+                vec![3, 2, 4]
+            } else {
+                vec![4, 3, 2]
+            };
+            let mut expected = Vec::<u8>::new();
+            crate::write_array(&mut expected, &shape, fortran_order, &data).unwrap();
+            assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn finish_on_drop() {
+        let mut buf = Cursor::new(Vec::<u8>::new());
+        {
+            let mut stream =
+                StreamWriter::<_, u8>::new(&mut buf, &[], false, &WriterOptions::default())
+                    .unwrap();
+            for i in 0u8..100u8 {
+                stream.write(&[i]).unwrap();
+            }
+        }
+        buf.set_position(0u64);
+        let header = crate::read_header(&mut buf).unwrap();
+        assert_eq!(header.shape, vec![100usize]);
+        assert_eq!(
+            crate::read_array::<u8>(&mut buf, &header).unwrap(),
+            (0u8..100u8).collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn incomplete_rows() {
+        let mut stream = StreamWriter::<_, i32>::new(
+            Cursor::new(Vec::new()),
+            &[2usize],
+            false,
+            &WriterOptions::default(),
+        )
+        .unwrap();
+        stream.write(&[1i32, 2i32, 3i32]).unwrap();
+        assert!(matches!(
+            stream.finish(),
+            Err(StreamWriterError::IncompleteRow(3usize, 2usize))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "not a multiple of the row length")]
+    fn incomplete_rows_on_drop() {
+        let mut stream = StreamWriter::<_, i32>::new(
+            Cursor::new(Vec::new()),
+            &[2usize],
+            false,
+            &WriterOptions::default(),
+        )
+        .unwrap();
+        stream.write(&[1i32]).unwrap();
+    }
+
+    #[test]
+    fn options() {
+        let options = WriterOptions {
+            version: Some(2u8),
+            alignment: 4096usize,
+            ..WriterOptions::default()
+        };
+        let mut stream =
+            StreamWriter::<_, u16>::new(Cursor::new(Vec::new()), &[2usize], false, &options)
+                .unwrap();
+        stream.write(&[1u16, 2u16]).unwrap();
+        let buf: Vec<u8> = stream.finish().unwrap().into_inner();
+        assert_eq!(buf.len(), 4096usize + 4usize);
+        assert_eq!(buf[6], 2u8);
+        let header = crate::read_header(&mut &buf[..]).unwrap();
+        assert_eq!(header.shape, vec![1usize, 2usize]);
+    }
+
+    /// A sink whose writes fail once broken.
+    struct Failing {
+        inner: Cursor<Vec<u8>>,
+        broken: bool,
+    }
+
+    impl std::io::Write for Failing {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.broken {
+                return Err(std::io::Error::other("disk full"));
+            }
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl std::io::Seek for Failing {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn io_errors_on_drop() {
+        let new = || {
+            let sink = Failing {
+                inner: Cursor::new(Vec::new()),
+                broken: false,
+            };
+            StreamWriter::<_, u8>::new(sink, &[], false, &WriterOptions::default()).unwrap()
+        };
+        let mut stream = new();
+        stream.inner.as_mut().unwrap().broken = true;
+        assert!(matches!(stream.finish(), Err(StreamWriterError::Io(_))));
+        // reported only through finish, and dropping does not panic
+        let mut stream = new();
+        stream.inner.as_mut().unwrap().broken = true;
+        drop(stream);
+    }
+}
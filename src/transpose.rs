@@ -0,0 +1,262 @@
+//! Out-of-core permutation of axes and conversion between C and Fortran order.
+
+use crate::error::TransposeError;
+use crate::layout::{Layout, Offsets, Order};
+use crate::selection::{self, Hyperslab, Runs};
+use crate::{writer, Header, HeaderInfo, WriterOptions};
+
+/// Splits the shape into tiles whose source and destination buffers fit in `memory_limit` bytes,
+/// halving the longest edge until they do.
+fn tile_shape(shape: &[usize], itemsize: usize, memory_limit: usize) -> Vec<usize> {
+    let mut tile: Vec<usize> = shape.iter().map(|&n: &usize| n.max(1usize)).collect();
+    loop {
+        let size: Option<usize> = tile
+            .iter()
+            .try_fold(2usize * itemsize, |size: usize, &n: &usize| {
+                size.checked_mul(n)
+            });
+        if size.is_some_and(|size: usize| size <= memory_limit) {
+            return tile;
+        }
+        match tile.iter_mut().max() {
+            Some(edge) if 1usize < *edge => *edge = edge.div_ceil(2usize),
+            _ => return tile,
+        }
+    }
+}
+
+/// Writes a new array `b` with `b.shape[k] == a.shape[axes[k]]`, as `numpy.transpose(a, axes)` does,
+/// stored in the given order, and returns its header.
+///
+/// The identity permutation with the opposite order flips 'fortran_order' without changing the array.
+/// The source is processed tile by tile, each of which is read with coalesced reads,
+/// rearranged in memory and written to its place in the destination,
+/// so that the memory in use is bounded by `memory_limit` (or two elements if larger)
+/// plus a buffer of at most 1 MiB, through which runs of the source separated by small gaps are read.
+/// The source is expected to start at the beginning of the stream and the destination to be empty,
+/// e.g. a newly created file. Any data type, including structured ones, is accepted.
+pub fn transpose<R, W>(
+    src: &mut R,
+    dst: &mut W,
+    axes: &[usize],
+    order: Order,
+    memory_limit: usize,
+) -> Result<HeaderInfo, TransposeError>
+where
+    R: std::io::Read + std::io::Seek,
+    W: std::io::Write + std::io::Seek,
+{
+    src.seek(std::io::SeekFrom::Start(0u64))?;
+    let src_info: HeaderInfo = crate::read_header_info(src)?;
+    let ndim: usize = src_info.header.shape.len();
+    let mut sorted: Vec<usize> = axes.to_vec();
+    sorted.sort_unstable();
+    if !sorted.iter().copied().eq(0usize..ndim) {
+        return Err(TransposeError::InvalidAxes(axes.to_vec(), ndim));
+    }
+    let header = Header {
+        descr: src_info.header.descr.clone(),
+        fortran_order: order.is_fortran_order(),
+        shape: axes
+            .iter()
+            .map(|&n: &usize| src_info.header.shape[n])
+            .collect(),
+    };
+    let buf: Vec<u8> = writer::prepare_header(&header, &WriterOptions::default())?;
+    let dst_info: HeaderInfo = crate::read_header_info(&mut buf.as_slice())?;
+    dst.seek(std::io::SeekFrom::Start(0u64))?;
+    dst.write_all(&buf)?;
+    let itemsize: usize = src_info.itemsize;
+    let src_order = Order::from_fortran_order(src_info.header.fortran_order);
+    let dst_axes: Vec<usize> = Layout::from_header(&header)?.storage_axes();
+    let tile: Vec<usize> = tile_shape(&header.shape, itemsize, memory_limit);
+    let grid: Vec<usize> = header
+        .shape
+        .iter()
+        .zip(tile.iter())
+        .map(|(&n, &edge): (&usize, &usize)| n.div_ceil(edge))
+        .collect();
+    // tiles are visited in the storage order of the destination
+    let grid: Layout = Layout::new(&grid, order, 1usize)?;
+    // the first tile is the largest one, which the buffers are sized for once
+    let tile_size: usize = tile.iter().product::<usize>() * itemsize;
+    let mut src_buf = Vec::<u8>::with_capacity(tile_size);
+    let mut dst_buf = Vec::<u8>::with_capacity(tile_size);
+    // relative to the payload, which follows the header just written
+    let mut position: Option<u64> = Some(0u64);
+    for tile_offset in 0usize..grid.len() {
+        let tile_index: Vec<usize> = grid.index_of(tile_offset).unwrap_or_default();
+        let dst_start: Vec<usize> = (0usize..ndim)
+            .map(|k: usize| tile_index[k] * tile[k])
+            .collect();
+        let dst_count: Vec<usize> = (0usize..ndim)
+            .map(|k: usize| tile[k].min(header.shape[k] - dst_start[k]))
+            .collect();
+        let mut src_start = vec![0usize; ndim];
+        let mut src_count = vec![0usize; ndim];
+        for (k, &n) in axes.iter().enumerate() {
+            src_start[n] = dst_start[k];
+            src_count[n] = dst_count[k];
+        }
+        src_buf.clear();
+        let src_slab = Hyperslab::block(&src_start, &src_count);
+        selection::read_runs(
+            src,
+            src_info.data_offset as u64,
+            Runs::new(&src_info, &src_slab)?,
+            &mut src_buf,
+        )?;
+        // strides of the source tile along the axes of the destination
+        let src_tile: Layout = Layout::new(&src_count, src_order, itemsize)?;
+        let strides: Vec<usize> = axes
+            .iter()
+            .map(|&n: &usize| src_tile.strides()[n])
+            .collect();
+        dst_buf.clear();
+        for offset in Offsets::new(&dst_count, &strides, &dst_axes) {
+            dst_buf.extend_from_slice(&src_buf[offset * itemsize..(offset + 1usize) * itemsize]);
+        }
+        let dst_slab = Hyperslab::block(&dst_start, &dst_count);
+        selection::write_runs(
+            dst,
+            dst_info.data_offset as u64,
+            Runs::new(&dst_info, &dst_slab)?,
+            &dst_buf,
+            &mut position,
+        )?;
+    }
+    dst.flush()?;
+    Ok(dst_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tile_shape, transpose};
+    use crate::error::TransposeError;
+    use crate::{Layout, Order};
+    use std::io::Cursor;
+
+    fn source(shape: &[usize], order: Order) -> Cursor<Vec<u8>> {
+        let count: usize = shape.iter().product();
+        let data: Vec<u32> = (0u32..count as u32).collect();
+        let mut buf = Vec::<u8>::new();
+        crate::write_array(&mut buf, shape, order.is_fortran_order(), &data).unwrap();
+        Cursor::new(buf)
+    }
+
+    /// Checks `b[j] == a[i]` with `i[axes[k]] == j[k]`, where `a[i]` is its offset in the source.
+    fn check(dst: &mut Cursor<Vec<u8>>, src_layout: &Layout, axes: &[usize]) {
+        dst.set_position(0u64);
+        let info = crate::validate_payload(dst).unwrap();
+        let data: Vec<u32> = crate::read_array(dst, &info.header).unwrap();
+        let dst_layout = Layout::from_header(&info.header).unwrap();
+        for (offset, &value) in data.iter().enumerate() {
+            let j: Vec<usize> = dst_layout.index_of(offset).unwrap();
+            let mut i = vec![0usize; j.len()];
+            for (k, &n) in axes.iter().enumerate() {
+                i[n] = j[k];
+            }
+            assert_eq!(src_layout.offset_of(&i), Some(value as usize));
+        }
+    }
+
+    #[test]
+    fn flip_order() {
+        for order in [Order::C, Order::F] {
+            let shape: [usize; 3] = [5usize, 7usize, 3usize];
+            let mut src = source(&shape, order);
+            let mut dst = Cursor::new(Vec::<u8>::new());
+            let opposite: Order = if order == Order::C {
+                Order::F
+            } else {
+                Order::C
+            };
+            let info = transpose(&mut src, &mut dst, &[0, 1, 2], opposite, 64usize).unwrap();
+            assert_eq!(info.header.shape, shape.to_vec());
+            assert_eq!(info.header.fortran_order, opposite.is_fortran_order());
+            check(
+                &mut dst,
+                &Layout::new(&shape, order, 4usize).unwrap(),
+                &[0, 1, 2],
+            );
+        }
+    }
+
+    #[test]
+    fn permute_axes() {
+        let shape: [usize; 3] = [4usize, 6usize, 9usize];
+        for axes in [[2usize, 0usize, 1usize], [1, 2, 0], [2, 1, 0]] {
+            for (order, memory_limit) in [(Order::C, 40usize), (Order::F, 1usize << 20)] {
+                let mut src = source(&shape, Order::C);
+                let mut dst = Cursor::new(Vec::<u8>::new());
+                let info = transpose(&mut src, &mut dst, &axes, order, memory_limit).unwrap();
+                let expected: Vec<usize> = axes.iter().map(|&n: &usize| shape[n]).collect();
+                assert_eq!(info.header.shape, expected);
+                check(
+                    &mut dst,
+                    &Layout::new(&shape, Order::C, 4usize).unwrap(),
+                    &axes,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_and_axes() {
+        assert_eq!(tile_shape(&[8, 8], 4, 1 << 10), vec![8, 8]);
+        assert_eq!(tile_shape(&[8, 8], 4, 128), vec![4, 4]);
+        assert_eq!(tile_shape(&[100, 3], 8, 0), vec![1, 1]);
+        let mut src = source(&[2usize, 3usize], Order::C);
+        let mut dst = Cursor::new(Vec::<u8>::new());
+        assert!(matches!(
+            transpose(&mut src, &mut dst, &[0, 0], Order::C, 64usize),
+            Err(TransposeError::InvalidAxes(..))
+        ));
+        let mut src = source(&[0usize, 3usize], Order::C);
+        let mut dst = Cursor::new(Vec::<u8>::new());
+        let info = transpose(&mut src, &mut dst, &[1, 0], Order::C, 64usize).unwrap();
+        assert_eq!(info.header.shape, vec![3usize, 0usize]);
+    }
+
+    /// A destination counting the seeks.
+    struct Seeks {
+        inner: Cursor<Vec<u8>>,
+        count: usize,
+    }
+
+    impl std::io::Write for Seeks {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl std::io::Seek for Seeks {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.count += 1usize;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn contiguous_tiles() {
+        // tiles of four whole rows, which follow each other in the destination
+        let shape: [usize; 2] = [8usize, 2usize];
+        let mut src = source(&shape, Order::C);
+        let mut dst = Seeks {
+            inner: Cursor::new(Vec::<u8>::new()),
+            count: 0usize,
+        };
+        transpose(&mut src, &mut dst, &[0, 1], Order::C, 64usize).unwrap();
+        // only the one before writing the header
+        assert_eq!(dst.count, 1usize);
+        check(
+            &mut dst.inner,
+            &Layout::new(&shape, Order::C, 4usize).unwrap(),
+            &[0, 1],
+        );
+    }
+}
@@ -2,11 +2,11 @@ mod dict;
 pub mod error;
 
 use crate::consts::{
-    HEADER_BLOCK_SIZE, MAGIC_STRING, MAX_HEADER_SIZE_V1, MINOR_VERSION, SIZE_HEADER_LEN,
+    HEADER_BLOCK_SIZE, MAGIC_STRING, MAX_HEADER_LEN_V1, MINOR_VERSION, SIZE_HEADER_LEN,
     SIZE_MAJOR_VERSION, SIZE_MINOR_VERSION,
 };
 use crate::error::WriteHeaderError;
-use std::io::Write;
+use crate::WriterOptions;
 
 pub struct BufferInfo {
     major_version: u8,
@@ -14,69 +14,122 @@ pub struct BufferInfo {
     padding_size: usize,
 }
 
-pub fn prepare_dictionary(header: &crate::Header) -> Result<Vec<u8>, WriteHeaderError> {
+/// Formats the dictionary as `numpy.lib.format` does,
+/// including the spare spaces for the growing axis.
+pub fn prepare_dictionary(
+    header: &crate::Header,
+    options: &WriterOptions,
+) -> Result<String, WriteHeaderError> {
     let descr: String = dict::prepare_descr(&header.descr)?;
     let fortran_order: String = dict::prepare_fortran_order(header.fortran_order)?;
     let shape: String = dict::prepare_shape(&header.shape)?;
     let dict: String = format!(
-        r#"{{'descr':{},'fortran_order':{},'shape':{}}}"#,
+        r#"{{'descr': {}, 'fortran_order': {}, 'shape': {}, }}"#,
         descr, fortran_order, shape
     );
-    Ok(dict.into_bytes())
+    // the outermost axis is the one which may grow
+    let growth_axis: Option<&usize> = if header.fortran_order {
+        header.shape.last()
+    } else {
+        header.shape.first()
+    };
+    let growth_size: usize = growth_axis.map_or(0usize, |&length: &usize| {
+        options
+            .growth_axis_max_digits
+            .saturating_sub(length.to_string().len())
+    });
+    let dict: String = dict + &" ".repeat(growth_size);
+    Ok(dict)
+}
+
+/// Size of the magic string, the version and HEADER_LEN, which precede the dictionary.
+pub fn prefix_size(major_version: u8) -> usize {
+    let size_header_len: usize =
+        SIZE_HEADER_LEN[if major_version == 1u8 { 0usize } else { 1usize }];
+    MAGIC_STRING.len() + SIZE_MAJOR_VERSION + SIZE_MINOR_VERSION + size_header_len
 }
 
-pub fn prepare_buffer_info(dict_len: usize) -> BufferInfo {
-    let header_size = {
-        let size_excluding_padding = dict_len
-            + MAGIC_STRING.len()
-            + SIZE_MAJOR_VERSION
-            + SIZE_MINOR_VERSION
-            + SIZE_HEADER_LEN[1];
-        let mut header_size = HEADER_BLOCK_SIZE;
-        while header_size < size_excluding_padding {
-            header_size += HEADER_BLOCK_SIZE;
-        }
-        header_size
+/// Encodes the dictionary in latin-1 for versions 1.0 and 2.0, and in utf-8 for 3.0.
+fn encode_dictionary(dict: &str, major_version: u8) -> Option<Vec<u8>> {
+    if major_version == 3u8 {
+        return Some(dict.as_bytes().to_vec());
+    }
+    dict.chars()
+        .map(|character: char| u8::try_from(character).ok())
+        .collect()
+}
+
+/// Decides the version and the padding so that the payload is aligned to `options.alignment`.
+///
+/// Following NumPy, at least one space precedes the terminating newline.
+/// Unless the version is forced, the version 2.0 is used only when the latin-1 header does not fit
+/// in the version 1.0, and the version 3.0 only when the header cannot be encoded in latin-1.
+pub fn prepare_buffer_info(
+    dict: &str,
+    options: &WriterOptions,
+) -> Result<(BufferInfo, Vec<u8>), WriteHeaderError> {
+    let alignment: usize = options.alignment;
+    if alignment == 0usize || !alignment.is_multiple_of(HEADER_BLOCK_SIZE) {
+        return Err(WriteHeaderError::InvalidAlignment(alignment));
+    }
+    // the first version and the fallbacks tried in order when it fails
+    let (first, rest): (u8, &[u8]) = match options.version {
+        None => (1u8, &[2u8, 3u8]),
+        Some(version @ 1u8..=3u8) => (version, &[]),
+        Some(version) => return Err(WriteHeaderError::InvalidVersion(version)),
     };
-    let major_version = if header_size <= MAX_HEADER_SIZE_V1 {
-        1u8
+    // the error of the last tried version is reported
+    rest.iter().fold(
+        fit_version(dict, first, alignment),
+        |result, &major_version: &u8| {
+            result.or_else(|_| fit_version(dict, major_version, alignment))
+        },
+    )
+}
+
+/// Pads the header of the given version so that the payload is aligned to `alignment`.
+fn fit_version(
+    dict: &str,
+    major_version: u8,
+    alignment: usize,
+) -> Result<(BufferInfo, Vec<u8>), WriteHeaderError> {
+    let dict: Vec<u8> = encode_dictionary(dict, major_version)
+        .ok_or(WriteHeaderError::UnencodableHeader(major_version))?;
+    let prefix_size: usize = prefix_size(major_version);
+    // dictionary and the terminating newline
+    let hlen: usize = dict.len() + 1usize;
+    let header_len: usize = hlen + alignment - (prefix_size + hlen) % alignment;
+    let max_header_len: usize = if major_version == 1u8 {
+        MAX_HEADER_LEN_V1
     } else {
-        2u8
+        u32::MAX as usize
     };
-    let header_len: usize = header_size
-        - MAGIC_STRING.len()
-        - SIZE_MAJOR_VERSION
-        - SIZE_MINOR_VERSION
-        - SIZE_HEADER_LEN[if major_version == 1u8 { 0usize } else { 1usize }];
-    let padding_size: usize = header_len - dict_len;
+    if max_header_len < header_len {
+        return Err(WriteHeaderError::HeaderTooLarge(header_len, major_version));
+    }
     let header_len_bytes: Vec<u8> = if major_version == 1u8 {
         (header_len as u16).to_le_bytes().to_vec()
     } else {
         (header_len as u32).to_le_bytes().to_vec()
     };
-    BufferInfo {
+    let buffer_info = BufferInfo {
         major_version,
         header_len: header_len_bytes,
-        padding_size,
-    }
+        padding_size: header_len - dict.len(),
+    };
+    Ok((buffer_info, dict))
 }
 
-pub fn prepare_padding(buffer_info: &BufferInfo) -> Result<Vec<u8>, WriteHeaderError> {
-    let padding_size: usize = buffer_info.padding_size;
-    if padding_size == 0usize {
-        return Err(WriteHeaderError::ZeroPaddingSize);
-    }
-    let mut buf = vec![0x20u8; padding_size.saturating_sub(1)];
+/// Spaces followed by the terminating newline.
+///
+/// The padding size is at least one by construction, as the newline is counted in `header_len`.
+pub fn prepare_padding(buffer_info: &BufferInfo) -> Vec<u8> {
+    let mut buf = vec![0x20u8; buffer_info.padding_size - 1usize];
     buf.push(0x0au8);
-    Ok(buf)
+    buf
 }
 
-pub fn write_all(
-    f: &mut std::fs::File,
-    buffer_info: &BufferInfo,
-    dict: &[u8],
-    padding: &[u8],
-) -> Result<(), WriteHeaderError> {
+pub fn prepare_buffer(buffer_info: &BufferInfo, dict: &[u8], padding: &[u8]) -> Vec<u8> {
     let mut buf = Vec::<u8>::new();
     buf.extend_from_slice(MAGIC_STRING);
     buf.extend_from_slice(&[buffer_info.major_version]);
@@ -84,6 +137,61 @@ pub fn write_all(
     buf.extend_from_slice(&buffer_info.header_len);
     buf.extend_from_slice(dict);
     buf.extend_from_slice(padding);
-    f.write_all(&buf)?;
-    Ok(())
+    buf
+}
+
+/// Serialises the whole header, from the magic string to the terminating newline.
+pub fn prepare_header(
+    header: &crate::Header,
+    options: &WriterOptions,
+) -> Result<Vec<u8>, WriteHeaderError> {
+    let dict: String = prepare_dictionary(header, options)?;
+    let (buffer_info, dict): (BufferInfo, Vec<u8>) = prepare_buffer_info(&dict, options)?;
+    let padding: Vec<u8> = prepare_padding(&buffer_info);
+    Ok(prepare_buffer(&buffer_info, &dict, &padding))
+}
+
+/// Serialises the header into exactly `header_len` bytes following HEADER_LEN,
+/// so that an existing header of the given version can be rewritten in-place.
+pub fn prepare_header_in_place(
+    header: &crate::Header,
+    major_version: u8,
+    header_len: usize,
+) -> Result<Vec<u8>, WriteHeaderError> {
+    let (size_header_len, max_header_len): (usize, usize) = match major_version {
+        1u8 => (SIZE_HEADER_LEN[0], MAX_HEADER_LEN_V1),
+        2u8 | 3u8 => (SIZE_HEADER_LEN[1], u32::MAX as usize),
+        _ => return Err(WriteHeaderError::InvalidVersion(major_version)),
+    };
+    if max_header_len < header_len {
+        return Err(WriteHeaderError::HeaderTooLarge(header_len, major_version));
+    }
+    // the spare spaces are given by the padding instead
+    let options = WriterOptions {
+        version: Some(major_version),
+        growth_axis_max_digits: 0usize,
+        ..WriterOptions::default()
+    };
+    let dict: String = prepare_dictionary(header, &options)?;
+    let dict: Vec<u8> = encode_dictionary(&dict, major_version)
+        .ok_or(WriteHeaderError::UnencodableHeader(major_version))?;
+    // dictionary and the terminating newline
+    if header_len < dict.len() + 1usize {
+        return Err(WriteHeaderError::InsufficientSpace(
+            dict.len() + 1usize,
+            header_len,
+        ));
+    }
+    let header_len_bytes: Vec<u8> = if size_header_len == SIZE_HEADER_LEN[0] {
+        (header_len as u16).to_le_bytes().to_vec()
+    } else {
+        (header_len as u32).to_le_bytes().to_vec()
+    };
+    let buffer_info = BufferInfo {
+        major_version,
+        header_len: header_len_bytes,
+        padding_size: header_len - dict.len(),
+    };
+    let padding: Vec<u8> = prepare_padding(&buffer_info);
+    Ok(prepare_buffer(&buffer_info, &dict, &padding))
 }
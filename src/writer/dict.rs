@@ -1,40 +1,14 @@
-use crate::consts::ENDIAN_SPECIFIERS;
+use crate::dtype::Descr;
 use crate::writer::error::ValidationError;
 
-/// Checks if the given string is a proper 'descr' value.
+/// Checks if the given data type is a proper 'descr' value and formats it as a Python literal.
 ///
-/// The 'descr' value is limited for simple data types (e.g., "'<f8'") for now.
-/// The value of 'descr' key requests the following things.
-///
-/// - It is a Pythonic string: namely the value is singly or doubly-quoted.
-/// - The first character after a quotation is an endian specifier.
-pub fn prepare_descr(descr: &str) -> Result<String, ValidationError> {
-    // reject empty string
-    if descr.is_empty() {
-        return Err(ValidationError::EmptyDescr);
-    }
-    // check if the descr value is doubly / singly quoted
-    {
-        let is_quoted = |q: char, s: &str| s.starts_with(q) && s.ends_with(q);
-        if !is_quoted('"', descr) && !is_quoted('\'', descr) {
-            return Err(ValidationError::unquoted_descr(descr));
-        }
-    }
-    // check the descr value is preceded by a proper endian specifier
-    {
-        let second_character: char = match descr.chars().nth(1) {
-            Some(character) => character,
-            None => {
-                return Err(ValidationError::no_endian_specifier(descr));
-            }
-        };
-        let is_expected_second_char = ENDIAN_SPECIFIERS
-            .iter()
-            .any(|&endian_specifier: &char| second_character == endian_specifier);
-        if !is_expected_second_char {
-            return Err(ValidationError::unexpected_endian_specifier(descr));
-        }
-    }
+/// Simple data types are quoted (e.g., "'<f8'"),
+/// while structured ones are given as a list of tuples (e.g., "[('x', '<f8', (3,))]").
+/// The kind, the item size and the byte order should be consistent with each other,
+/// see [`Descr::validate`].
+pub fn prepare_descr(descr: &Descr) -> Result<String, ValidationError> {
+    descr.validate().map_err(ValidationError::InvalidDType)?;
     Ok(descr.to_string())
 }
 
@@ -47,27 +21,52 @@ pub fn prepare_fortran_order(fortran_order: bool) -> Result<String, ValidationEr
     Ok(fortran_order)
 }
 
+/// Formats the shape as a Python tuple, e.g., "()", "(3,)" or "(3, 5)".
 pub fn prepare_shape(shape: &[usize]) -> Result<String, ValidationError> {
-    if shape.iter().any(|&elem| elem == 0usize) {
-        return Err(ValidationError::NonPositiveShape(shape.to_vec()));
-    }
-    let shape = shape
-        .iter()
-        .map(|&value: &usize| value.to_string())
-        .collect::<Vec<String>>()
-        .join(",");
+    let shape: String = match shape {
+        [length] => format!("{},", length),
+        _ => shape
+            .iter()
+            .map(|&value: &usize| value.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
     Ok(format!("({})", shape))
 }
 
 #[cfg(test)]
 mod tests {
     use super::{prepare_descr, prepare_fortran_order, prepare_shape};
+    use crate::dtype::{ByteOrder, DType, Descr, Field, Kind};
+    use crate::error::DTypeError;
     use crate::writer::error::ValidationError;
 
     #[test]
     fn prepare_descr_normal() {
-        assert_eq!(prepare_descr(r#"'<i8'"#), Ok(r#"'<i8'"#.to_string()));
-        assert_eq!(prepare_descr(r#""<i8""#), Ok(r#""<i8""#.to_string()));
+        let descr = Descr::Simple(DType::new(ByteOrder::Little, Kind::Int, 8usize));
+        assert_eq!(prepare_descr(&descr), Ok(r#"'<i8'"#.to_string()));
+        let descr = Descr::Simple(DType::new(ByteOrder::Big, Kind::Float, 4usize));
+        assert_eq!(prepare_descr(&descr), Ok(r#"'>f4'"#.to_string()));
+        let descr = Descr::Simple(DType::new(ByteOrder::NotApplicable, Kind::Bool, 1usize));
+        assert_eq!(prepare_descr(&descr), Ok(r#"'|b1'"#.to_string()));
+        let descr = Descr::Simple(DType::new(ByteOrder::Little, Kind::Complex, 16usize));
+        assert_eq!(prepare_descr(&descr), Ok(r#"'<c16'"#.to_string()));
+        let descr = Descr::Record(vec![
+            Field {
+                name: "pos".to_string(),
+                descr: Descr::Simple(DType::new(ByteOrder::Little, Kind::Float, 8usize)),
+                shape: vec![3usize],
+            },
+            Field {
+                name: "id".to_string(),
+                descr: Descr::Simple(DType::new(ByteOrder::Little, Kind::Int, 4usize)),
+                shape: vec![],
+            },
+        ]);
+        assert_eq!(
+            prepare_descr(&descr),
+            Ok(r#"[('pos', '<f8', (3,)), ('id', '<i4')]"#.to_string())
+        );
     }
 
     #[test]
@@ -79,44 +78,39 @@ mod tests {
     #[test]
     fn prepare_shape_normal() {
         assert_eq!(prepare_shape(&[]), Ok(r"()".to_string()));
-        assert_eq!(prepare_shape(&[1usize]), Ok(r"(1)".to_string()));
-        assert_eq!(prepare_shape(&[1usize, 2usize]), Ok(r"(1,2)".to_string()));
+        assert_eq!(prepare_shape(&[1usize]), Ok(r"(1,)".to_string()));
+        assert_eq!(prepare_shape(&[1usize, 2usize]), Ok(r"(1, 2)".to_string()));
+        assert_eq!(
+            prepare_shape(&[3usize, 4usize, 5usize]),
+            Ok(r"(3, 4, 5)".to_string())
+        );
     }
 
     #[test]
     fn prepare_descr_corner() {
-        assert_eq!(prepare_descr(r#""#), Err(ValidationError::EmptyDescr));
+        let descr = Descr::Simple(DType::new(ByteOrder::NotApplicable, Kind::Int, 8usize));
         assert_eq!(
-            prepare_descr(r#"<i8"#),
-            Err(ValidationError::unquoted_descr("<i8"))
+            prepare_descr(&descr),
+            Err(ValidationError::InvalidDType(
+                DTypeError::missing_byte_order("|i8")
+            ))
+        );
+        let descr = Descr::Simple(DType::new(ByteOrder::Little, Kind::Bool, 2usize));
+        assert_eq!(
+            prepare_descr(&descr),
+            Err(ValidationError::InvalidDType(DTypeError::invalid_itemsize(
+                "|b2"
+            )))
         );
-        let patterns = [r#"'"#, r#"""#];
-        for pattern in patterns.iter() {
-            assert_eq!(
-                prepare_descr(pattern),
-                Err(ValidationError::no_endian_specifier(pattern))
-            );
-        }
-        let patterns = [r#"'hoge'"#, r#""hoge""#, r#"'''"#, r#"""""#];
-        for pattern in patterns.iter() {
-            assert_eq!(
-                prepare_descr(pattern),
-                Err(ValidationError::unexpected_endian_specifier(pattern))
-            );
-        }
     }
 
     #[test]
     fn prepare_shape_corner() {
-        let buf = [0usize];
-        assert_eq!(
-            prepare_shape(&buf),
-            Err(ValidationError::NonPositiveShape(buf.to_vec()))
-        );
-        let buf = [1usize, 0usize, 2usize];
+        // zero-sized dimensions are allowed as in NumPy
+        assert_eq!(prepare_shape(&[0usize]), Ok(r"(0,)".to_string()));
         assert_eq!(
-            prepare_shape(&buf),
-            Err(ValidationError::NonPositiveShape(buf.to_vec()))
+            prepare_shape(&[1usize, 0usize, 2usize]),
+            Ok(r"(1, 0, 2)".to_string())
         );
     }
 }